@@ -0,0 +1,145 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Helpers for generating parsers from a `build.rs` script.
+//!
+//! ```no_run
+//! # use parsify_core as parsify;
+//! // in build.rs
+//! parsify::build::compile_grammar("src/expr.bnf");
+//! ```
+//!
+//! The generated file is then included where the parser should live:
+//!
+//! ```ignore
+//! mod expr {
+//!   include!(concat!(env!("OUT_DIR"), "/expr.rs"));
+//! }
+//! ```
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use crate::diagnostic::{Diagnostic, Renderer};
+use crate::grammar::{Grammar, GrammarError};
+use crate::language::{Language, LanguageError};
+use crate::template::TemplateError;
+
+#[derive(Debug)]
+pub enum BuildError {
+  NoOutDir,
+  Io(PathBuf, io::Error),
+  Language(LanguageError),
+  Template(TemplateError),
+  Grammar(PathBuf, String, GrammarError),
+  // the grammar parsed, but cannot be turned into a deterministic parser.
+  Conflicts(PathBuf, String, Vec<Diagnostic>),
+}
+
+/// Generates a Rust parser for `grammar` into `$OUT_DIR/<grammar file stem>.rs` and returns its
+/// path. Panics with the formatted diagnostics if the grammar has errors or conflicts, which fails
+/// the build.
+pub fn compile_grammar(grammar: impl AsRef<Path>) -> PathBuf {
+  let grammar = grammar.as_ref();
+  let file_name = format!("{}.rs", grammar.file_stem().unwrap_or_default().to_string_lossy());
+  compile_grammar_to(grammar, None::<&Path>, file_name.as_str())
+}
+
+/// Like [`compile_grammar`], but with an optional language spec and output file name.
+pub fn compile_grammar_to(grammar: impl AsRef<Path>, language_spec: Option<impl AsRef<Path>>, file_name: &str) -> PathBuf {
+  match try_compile_grammar(grammar.as_ref(), language_spec.as_ref().map(|x| x.as_ref()), file_name) {
+    Ok(path) => path,
+    Err(error) => {
+      eprintln!("{}", error);
+      panic!("parsify failed to generate {}", file_name);
+    }
+  }
+}
+
+pub fn try_compile_grammar(grammar: &Path, language_spec: Option<&Path>, file_name: &str) -> Result<PathBuf, BuildError> {
+  println!("cargo:rerun-if-changed={}", grammar.display());
+
+  let language = match language_spec {
+    None => Language::rust(),
+    Some(path) => {
+      println!("cargo:rerun-if-changed={}", path.display());
+      Language::from_file(path).map_err(BuildError::Language)?
+    }
+  };
+
+  let source = fs::read_to_string(grammar).map_err(|x| BuildError::Io(grammar.to_path_buf(), x))?;
+  let parsed = match Grammar::parse(source.as_str()) {
+    Ok(parsed) => parsed,
+    Err(error) => return Err(BuildError::Grammar(grammar.to_path_buf(), source, error)),
+  };
+
+  for token in parsed.undefined_symbols() {
+    println!("cargo:warning={}:{}:{}: undefined non-terminal `{}`", grammar.display(), token.span.start.line_num, token.span.start.col, token.value);
+  }
+
+  if !parsed.conflicts().is_empty() {
+    let diagnostics = parsed.warnings().into_iter().filter(|x| x.code == "P0004").collect();
+    return Err(BuildError::Conflicts(grammar.to_path_buf(), source, diagnostics));
+  }
+
+  let out_dir = env::var_os("OUT_DIR").ok_or(BuildError::NoOutDir)?;
+  let output_path = Path::new(&out_dir).join(file_name);
+
+  let file_stem = output_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+  let files = crate::generate_files(&parsed, &language, file_stem.as_str()).map_err(BuildError::Template)?;
+
+  for file in files {
+    let path = file.path(&output_path);
+    fs::write(&path, file.source).map_err(|x| BuildError::Io(path, x))?;
+  }
+
+  Ok(output_path)
+}
+
+impl Display for BuildError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BuildError::NoOutDir => write!(f, "OUT_DIR is not set, parsify::build must be called from a build script"),
+      BuildError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+      BuildError::Language(error) => write!(f, "{}", error.render(false)),
+      BuildError::Template(error) => write!(f, "{}", error),
+      BuildError::Grammar(path, source, error) => write!(f, "{}", error.render(path.display().to_string().as_str(), source, false)),
+      BuildError::Conflicts(path, source, diagnostics) => {
+        let renderer = Renderer::new(path.display().to_string(), source, false);
+        for diagnostic in diagnostics {
+          writeln!(f, "{}", renderer.render(diagnostic))?;
+        }
+
+        write!(f, "{} has {} conflict(s)", path.display(), diagnostics.len())
+      }
+    }
+  }
+}
+
+impl Error for BuildError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      BuildError::Io(_, error) => Some(error),
+      BuildError::Language(error) => Some(error),
+      BuildError::Template(error) => Some(error),
+      BuildError::Grammar(_, _, error) => Some(error),
+      _ => None,
+    }
+  }
+}
@@ -0,0 +1,420 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::IsTerminal;
+use clap::ValueEnum;
+use serde::Serialize;
+use unicode_width::UnicodeWidthChar;
+use crate::scanner::Span;
+
+// tabs are rendered as this many spaces in source snippets.
+const TAB_WIDTH: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorChoice {
+  Auto,
+  Always,
+  Never,
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+  pub span: Span,
+  pub message: String,
+  pub is_primary: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub code: &'static str,
+  pub message: String,
+  pub labels: Vec<Label>,
+  pub notes: Vec<String>,
+  pub help: Vec<String>,
+}
+
+const EXPLANATIONS: [(&str, &str); 11] = [
+  ("P0001", "\
+A character that cannot start any grammar token was found.
+
+Grammar files may only contain identifiers (`name` or `<name>`), quoted
+literals (`'x'` or `\"x\"`), the definition operators `::=` and `:`, the
+alternation bar `|`, the rule terminators `;` and `.`, and `//` comments.
+
+Erroneous example:
+
+    expr ::= term # expr;
+
+Remove the character or quote it to turn it into a terminal:
+
+    expr ::= term '#' expr;
+"),
+  ("P0002", "\
+A token was still being scanned when its line or the file ended.
+
+This usually means a literal is missing its closing quote, an `<identifier>`
+is missing its closing `>`, or a `::=` was cut short.
+
+Erroneous example:
+
+    expr ::= 'x;
+
+Close the literal:
+
+    expr ::= 'x';
+"),
+  ("P0003", "\
+The grammar contains a token where the meta-grammar does not allow it.
+
+Every rule has the shape `name ::= alternative | alternative ;`, where each
+alternative is a possibly empty list of identifiers and literals. The most
+common cause is a rule missing its terminating `;` or `.`, which makes the
+next rule's name look like part of the previous rule.
+
+Erroneous example:
+
+    a ::= 'x' b
+    b ::= 'y';
+
+Terminate every rule:
+
+    a ::= 'x' b;
+    b ::= 'y';
+"),
+  ("P0004", "\
+Two alternatives of the same rule can start with the same token, so a
+recursive descent parser cannot decide which one to take by looking at a
+single token. The generated parser will always pick the first alternative.
+
+Erroneous example:
+
+    a ::= 'x' 'y' | 'x' 'z';
+
+Left-factor the common prefix into a new rule:
+
+    a ::= 'x' a_rest;
+    a_rest ::= 'y' | 'z';
+"),
+  ("P0005", "\
+A name written in angle brackets is used on the right-hand side of a rule,
+but no rule defines it.
+
+Bare identifiers without a rule are treated as terminal token kinds, but
+`<name>` is reserved for non-terminals, so this is most likely a typo or a
+missing rule. The name is still matched as a terminal.
+
+Erroneous example:
+
+    <expr> ::= <term> '+' <expr> | <term>;
+
+Define the missing rule:
+
+    <term> ::= NUMBER;
+"),
+  ("P0006", "\
+Two rules have names that become the same identifier in generated code.
+
+`<` and `>` are dropped from rule names and `-` becomes `_`, so `a-b`, `<a-b>`
+and `a_b` all name the function `a_b`. Templates may also capitalize names,
+which turns both `expr` and `Expr` into `parseExpr`. The generated parser keeps
+the first rule's name and appends `_` or a number to the later one, which works
+but makes the generated code hard to follow.
+
+Erroneous example:
+
+    list-item ::= ITEM;
+    list_item ::= ITEM ',';
+
+Give the rules distinct names:
+
+    list-item ::= ITEM;
+    trailing-item ::= ITEM ',';
+"),
+  ("L0001", "\
+A language spec is not valid JSON.
+
+The location points at the first character the JSON parser could not make
+sense of. Common causes are trailing commas, unescaped quotes or newlines
+inside strings, and missing commas between fields.
+"),
+  ("L0002", "\
+A language spec is missing a field that has no default.
+
+Every spec needs a `template`, the lines the parser is rendered from. All other
+fields, `extension`, `files`, `partials`, `options`, `reserved`, `features`,
+`indent` and `line_ending`, may be left out.
+"),
+  ("L0003", "\
+A field of a language spec has the wrong type.
+
+`template` is an array of lines, `files` maps output file suffixes and
+`partials` maps partial names to arrays of lines, `options` maps option
+names to string values, `reserved` is an array of words, `features` an
+array of feature names like `\"token_enum\"` and `indent.width` a number of
+spaces of at least 1.
+
+Erroneous example:
+
+    \"template\": \"fn parse() {}\"
+
+Write the template as an array of lines:
+
+    \"template\": [\"fn parse() {}\"]
+"),
+  ("L0004", "\
+A language spec contains a field parsify does not know.
+
+This is usually a misspelled field name, or a spec written for an older
+parsify that described languages with prefix and suffix wrappers instead of a
+template. Compare the spec with the bundled `langs/rust.json`.
+"),
+  ("L0005", "\
+The template or a partial of a language spec is malformed.
+
+Every `{{#each}}`, `{{#if}}` and `{{#unless}}` block must be closed by the
+matching `{{/each}}`, `{{/if}}` or `{{/unless}}`, `{{else}}` may only appear
+once inside `{{#if}}` or `{{#unless}}`, and every `{{> name}}` must name a
+partial defined under `partials`. Values may only be passed through the
+`upper`, `lower`, `capitalize`, `pascal` and `identifier` filters.
+
+Erroneous example:
+
+    \"{{#each rules}}\", \"fn {{name}}() {}\", \"{{/if}}\"
+
+Close blocks in the order they were opened:
+
+    \"{{#each rules}}\", \"fn {{name}}() {}\", \"{{/each}}\"
+"),
+];
+
+pub fn explain(code: &str) -> Option<&'static str> {
+  let code = code.to_uppercase();
+  EXPLANATIONS.iter().find(|x| x.0 == code).map(|x| x.1)
+}
+
+impl Label {
+  pub fn primary(span: Span, message: String) -> Self {
+    Label { span, message, is_primary: true }
+  }
+
+  pub fn secondary(span: Span, message: String) -> Self {
+    Label { span, message, is_primary: false }
+  }
+}
+
+impl Diagnostic {
+  pub fn new(severity: Severity, code: &'static str, message: String) -> Self {
+    Diagnostic {
+      severity,
+      code,
+      message,
+      labels: vec![],
+      notes: vec![],
+      help: vec![],
+    }
+  }
+
+  pub fn with_label(mut self, label: Label) -> Self {
+    self.labels.push(label);
+    self
+  }
+
+  pub fn with_note(mut self, note: String) -> Self {
+    self.notes.push(note);
+    self
+  }
+
+  pub fn with_help(mut self, help: String) -> Self {
+    self.help.push(help);
+    self
+  }
+
+  pub fn primary_span(&self) -> Option<Span> {
+    self.labels.iter().find(|x| x.is_primary).map(|x| x.span)
+  }
+}
+
+/// Renders diagnostics as annotated source snippets.
+pub struct Renderer {
+  file_path: String,
+  lines: Vec<String>,
+  color: bool,
+}
+
+impl ColorChoice {
+  pub fn should_color(&self) -> bool {
+    match self {
+      ColorChoice::Always => true,
+      ColorChoice::Never => false,
+      ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal(),
+    }
+  }
+}
+
+fn char_width(character: char) -> usize {
+  if character == '\t' {
+    TAB_WIDTH
+  } else {
+    character.width().unwrap_or(0)
+  }
+}
+
+fn expand_tabs(line: &str) -> String {
+  line.replace('\t', " ".repeat(TAB_WIDTH).as_str())
+}
+
+// display column (0 based) of the 1 based character column `col` on `line`.
+fn display_col(line: &str, col: usize) -> usize {
+  line.chars().take(col.saturating_sub(1)).map(char_width).sum()
+}
+
+impl Renderer {
+  pub fn new(file_path: String, file: &str, color: bool) -> Self {
+    Renderer {
+      file_path,
+      lines: file.split('\n').map(|x| x.trim_end_matches('\r').to_string()).collect(),
+      color,
+    }
+  }
+
+  fn paint(&self, style: &str, text: &str) -> String {
+    if self.color {
+      format!("{}{}{}", style, text, RESET)
+    } else {
+      text.to_string()
+    }
+  }
+
+  pub fn render(&self, diagnostic: &Diagnostic) -> String {
+    let (severity, style) = match diagnostic.severity {
+      Severity::Error => ("error", RED),
+      Severity::Warning => ("warning", YELLOW),
+    };
+
+    let mut result = format!(
+      "{}{}\n",
+      self.paint(style, format!("{}[{}]", severity, diagnostic.code).as_str()),
+      self.paint(BOLD, format!(": {}", diagnostic.message).as_str()),
+    );
+
+    let max_line = diagnostic.labels.iter().map(|x| x.span.end.line_num).max().unwrap_or(1);
+    let gutter = " ".repeat(max_line.to_string().len());
+    let bar = self.paint(BLUE, "|");
+
+    if let Some(span) = diagnostic.primary_span() {
+      result.push_str(format!("{}{} {}:{}:{}\n", gutter, self.paint(BLUE, "-->"), self.file_path, span.start.line_num, span.start.col).as_str());
+    } else {
+      result.push_str(format!("{}{} {}\n", gutter, self.paint(BLUE, "-->"), self.file_path).as_str());
+    }
+
+    if !diagnostic.labels.is_empty() {
+      result.push_str(format!("{} {}\n", gutter, bar).as_str());
+    }
+
+    let mut line_nums: Vec<usize> = vec![];
+    for label in &diagnostic.labels {
+      for line_num in label.span.start.line_num..=label.span.end.line_num {
+        if !line_nums.contains(&line_num) {
+          line_nums.push(line_num);
+        }
+      }
+    }
+    line_nums.sort();
+
+    let mut prev_line_num: Option<usize> = None;
+    for line_num in line_nums {
+      if let Some(prev) = prev_line_num {
+        if line_num > prev + 1 {
+          result.push_str(format!("{}\n", self.paint(BLUE, "...")).as_str());
+        }
+      }
+      prev_line_num = Some(line_num);
+
+      // the span may point just past the end of the file.
+      let line = match line_num.checked_sub(1).and_then(|x| self.lines.get(x)) {
+        None => "",
+        Some(line) => line.as_str(),
+      };
+
+      result.push_str(format!("{} {} {}\n", self.paint(BLUE, format!("{:>width$}", line_num, width = gutter.len()).as_str()), bar, expand_tabs(line)).as_str());
+
+      for label in &diagnostic.labels {
+        if line_num < label.span.start.line_num || line_num > label.span.end.line_num {
+          continue;
+        }
+
+        result.push_str(format!("{} {} {}\n", gutter, bar, self.underline(line, line_num, label, style)).as_str());
+      }
+    }
+
+    if !diagnostic.notes.is_empty() || !diagnostic.help.is_empty() {
+      result.push_str(format!("{} {}\n", gutter, bar).as_str());
+    }
+
+    for note in &diagnostic.notes {
+      result.push_str(format!("{} {} {}\n", gutter, self.paint(BLUE, "="), indent_continuation(format!("note: {}", note).as_str(), gutter.len() + 3)).as_str());
+    }
+
+    for help in &diagnostic.help {
+      result.push_str(format!("{} {} {}\n", gutter, self.paint(BLUE, "="), indent_continuation(format!("help: {}", help).as_str(), gutter.len() + 3)).as_str());
+    }
+
+    result
+  }
+
+  fn underline(&self, line: &str, line_num: usize, label: &Label, style: &str) -> String {
+    let span = label.span;
+    let first_non_blank = line.chars().take_while(|x| x.is_whitespace()).count() + 1;
+
+    let start = if line_num == span.start.line_num { span.start.col } else { first_non_blank };
+    let end = if line_num == span.end.line_num { span.end.col } else { line.chars().count() + 1 };
+
+    let start_display = display_col(line, start);
+    let width = display_col(line, end).saturating_sub(start_display).max(1);
+
+    let (marker, marker_style) = if label.is_primary { ("^", style) } else { ("-", BLUE) };
+    let mut result = " ".repeat(start_display);
+    result.push_str(self.paint(marker_style, marker.repeat(width).as_str()).as_str());
+
+    // only annotate the last line of a multi-line label.
+    if line_num == span.end.line_num && !label.message.is_empty() {
+      result.push(' ');
+      result.push_str(self.paint(marker_style, label.message.as_str()).as_str());
+    }
+
+    result
+  }
+}
+
+fn indent_continuation(text: &str, indent: usize) -> String {
+  text.replace('\n', format!("\n{}", " ".repeat(indent)).as_str())
+}
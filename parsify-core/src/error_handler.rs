@@ -0,0 +1,217 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::diagnostic::{Diagnostic, Label, Renderer, Severity};
+use crate::parser::ParserError;
+use crate::productions::{Ambiguity, NonTerminal};
+use crate::scanner::{ScanError, Span, Token};
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageFormat {
+  Human,
+  Json,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct JsonSpan {
+  line_start: usize,
+  column_start: usize,
+  line_end: usize,
+  column_end: usize,
+  label: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct JsonDiagnostic {
+  severity: Severity,
+  code: &'static str,
+  message: String,
+  file: String,
+  span: Option<JsonSpan>,
+  related: Vec<JsonSpan>,
+  notes: Vec<String>,
+  suggestions: Vec<String>,
+}
+
+/// Prints diagnostics for a single grammar file in the requested format.
+pub struct Reporter {
+  format: MessageFormat,
+  file_path: String,
+  renderer: Renderer,
+}
+
+impl From<&Label> for JsonSpan {
+  fn from(label: &Label) -> Self {
+    JsonSpan {
+      line_start: label.span.start.line_num,
+      column_start: label.span.start.col,
+      line_end: label.span.end.line_num,
+      column_end: label.span.end.col,
+      label: label.message.clone(),
+    }
+  }
+}
+
+impl Reporter {
+  pub fn new(format: MessageFormat, color: bool, file_path: String, file: &str) -> Self {
+    Reporter {
+      format,
+      renderer: Renderer::new(file_path.clone(), file, color),
+      file_path,
+    }
+  }
+
+  pub fn emit(&self, diagnostic: &Diagnostic) {
+    match self.format {
+      MessageFormat::Human => eprintln!("{}", self.renderer.render(diagnostic)),
+      MessageFormat::Json => println!("{}", serde_json::to_string(&self.to_json(diagnostic)).unwrap()),
+    }
+  }
+
+  fn to_json(&self, diagnostic: &Diagnostic) -> JsonDiagnostic {
+    JsonDiagnostic {
+      severity: diagnostic.severity,
+      code: diagnostic.code,
+      message: diagnostic.message.clone(),
+      file: self.file_path.clone(),
+      span: diagnostic.labels.iter().find(|x| x.is_primary).map(|x| x.into()),
+      related: diagnostic.labels.iter().filter(|x| !x.is_primary).map(|x| x.into()).collect(),
+      notes: diagnostic.notes.clone(),
+      suggestions: diagnostic.help.clone(),
+    }
+  }
+}
+
+fn escape_char(seen: char) -> String {
+  match seen {
+    '\n' => "\\n".to_string(),
+    '\t' => "\\t".to_string(),
+    _ => seen.to_string(),
+  }
+}
+
+// human readable names for the meta-grammar's token kinds.
+fn describe_kind(kind: &str) -> &str {
+  match kind {
+    "ID" => "identifier",
+    "TERM" => "literal",
+    "EQUALS" => "`::=`",
+    "END" => "`;`",
+    "EOF" => "end of file",
+    "|" => "`|`",
+    _ => kind,
+  }
+}
+
+pub fn scan_error_diagnostic(error: &ScanError) -> Diagnostic {
+  match error {
+    ScanError::UnexpectedChar(expected, seen, at) => {
+      let mut diagnostic = Diagnostic::new(Severity::Error, "P0001", format!("unexpected character `{}`", escape_char(*seen)));
+
+      if *expected != '_' {
+        diagnostic = diagnostic
+          .with_label(Label::primary(Span::at(*at), format!("expected `{}`", escape_char(*expected))))
+          .with_help(format!("insert `{}` here", escape_char(*expected)));
+      } else {
+        diagnostic = diagnostic
+          .with_label(Label::primary(Span::at(*at), "not valid in a grammar".to_string()))
+          .with_note("grammar tokens are identifiers, quoted literals, `::=`, `:`, `|`, `;`, `.` and `//` comments".to_string());
+      }
+
+      diagnostic
+    }
+    ScanError::NoMoreChars(at) => {
+      Diagnostic::new(Severity::Error, "P0002", format!("line {} ended unexpectedly", at.line_num))
+        .with_label(Label::primary(Span::at(*at), "token is not finished".to_string()))
+        .with_help("check for a missing closing quote or `>`".to_string())
+    }
+  }
+}
+
+pub fn parse_error_diagnostic(error: &ParserError) -> Diagnostic {
+  match error {
+    ParserError::UnexpectedToken(token, expected) => {
+      let expected_names: Vec<&str> = expected.iter().map(|x| describe_kind(x)).collect();
+      let found = if token.kind == "EOF" { "end of file".to_string() } else { format!("token `{}`", token.value) };
+
+      let mut diagnostic = Diagnostic::new(Severity::Error, "P0003", format!("unexpected {}", found))
+        .with_label(Label::primary(token.span, format!("expected {}", expected_names.join(", "))));
+
+      if expected.contains(&"END") {
+        diagnostic = diagnostic.with_help("rules must be terminated with `;` or `.`".to_string());
+      }
+
+      diagnostic
+    }
+  }
+}
+
+pub fn undefined_symbol_diagnostic(token: &Token) -> Diagnostic {
+  Diagnostic::new(Severity::Warning, "P0005", format!("undefined non-terminal `{}`", token.value))
+    .with_label(Label::primary(token.span, "no rule defines this name".to_string()))
+    .with_note("it will be matched as a terminal token".to_string())
+}
+
+pub fn ambiguity_diagnostic(ambiguity: &Ambiguity) -> Diagnostic {
+  let mut diagnostic = Diagnostic::new(Severity::Warning, "P0004", format!("ambiguous alternatives in {}", ambiguity.non_terminal))
+    .with_label(Label::primary(ambiguity.span, format!("more than one alternative can start with {}", ambiguity.tokens.join(", "))));
+
+  for span in &ambiguity.related {
+    diagnostic = diagnostic.with_label(Label::secondary(*span, "conflicting alternative".to_string()));
+  }
+
+  diagnostic
+    .with_note("the generated parser always takes the first matching alternative".to_string())
+    .with_help("left-factor the alternatives so each one starts with a distinct token".to_string())
+}
+
+pub fn name_collision_diagnostic(first: &NonTerminal, second: &NonTerminal) -> Diagnostic {
+  Diagnostic::new(Severity::Warning, "P0006", format!("rules `{}` and `{}` have the same generated name", first.name, second.name))
+    .with_label(Label::primary(second.span, format!("generates the same name as `{}`", first.name)))
+    .with_label(Label::secondary(first.span, "first rule with this name".to_string()))
+    .with_note("the generated parser renames the later rule by appending `_` or a number".to_string())
+    .with_help("rename one of the rules".to_string())
+}
+
+// `line:col: message (label)`, for contexts without the source text.
+fn write_short(f: &mut Formatter<'_>, diagnostic: &Diagnostic) -> std::fmt::Result {
+  match diagnostic.labels.iter().find(|x| x.is_primary) {
+    Some(label) => write!(f, "{}:{}: {} ({})", label.span.start.line_num, label.span.start.col, diagnostic.message, label.message),
+    None => write!(f, "{}", diagnostic.message),
+  }
+}
+
+impl Display for ScanError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write_short(f, &scan_error_diagnostic(self))
+  }
+}
+
+impl Error for ScanError {}
+
+impl Display for ParserError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write_short(f, &parse_error_diagnostic(self))
+  }
+}
+
+impl Error for ParserError {}
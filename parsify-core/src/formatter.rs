@@ -0,0 +1,534 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use clap::ValueEnum;
+use crate::grammar::GrammarError;
+use crate::parser::Parser;
+use crate::scanner::{Scanner, Token};
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operator {
+  /// `::=`
+  #[value(name = "::=")]
+  Bnf,
+  /// `:`
+  #[value(name = ":")]
+  Colon,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Terminator {
+  /// `;`
+  #[value(name = ";")]
+  Semicolon,
+  /// `.`
+  #[value(name = ".")]
+  Period,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Layout {
+  /// keep rules on one line unless they contain comments or exceed the width
+  Auto,
+  /// always put all alternatives on one line
+  Inline,
+  /// always put each alternative on its own line with aligned `|`
+  Aligned,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NameStyle {
+  /// leave non-terminal names as written
+  Keep,
+  /// write non-terminals as `<name>`
+  Angle,
+  /// write non-terminals as `name`
+  Bare,
+}
+
+/// How [`format`] lays out a grammar.
+pub struct FormatOptions {
+  pub operator: Operator,
+  pub terminator: Terminator,
+  pub layout: Layout,
+  pub width: usize,
+  pub names: NameStyle,
+  pub group_rules: bool,
+}
+
+/// Why a grammar could not be formatted.
+#[derive(Debug)]
+pub enum FormatError {
+  /// The grammar does not scan or parse, the formatter relies on its structure.
+  Invalid(GrammarError),
+  /// Renaming non-terminals for [`FormatOptions::names`] would give two of them the same name.
+  NameClash(String),
+}
+
+#[derive(Default)]
+struct Alternative {
+  leading_comments: Vec<String>,
+  tokens: Vec<String>,
+  trailing_comment: Option<String>,
+}
+
+struct Rule {
+  name: String,
+  alternatives: Vec<Alternative>,
+  // comments between the last alternative and the terminator.
+  end_comments: Vec<String>,
+  trailing_comment: Option<String>,
+}
+
+enum Item {
+  Blank,
+  Comment(String),
+  Rule(Rule),
+}
+
+impl Operator {
+  fn as_str(&self) -> &'static str {
+    match self {
+      Operator::Bnf => "::=",
+      Operator::Colon => ":",
+    }
+  }
+}
+
+impl Terminator {
+  fn as_str(&self) -> &'static str {
+    match self {
+      Terminator::Semicolon => ";",
+      Terminator::Period => ".",
+    }
+  }
+}
+
+impl Rule {
+  fn has_comments(&self) -> bool {
+    !self.end_comments.is_empty()
+      || self.alternatives.iter().any(|x| !x.leading_comments.is_empty() || x.trailing_comment.is_some())
+  }
+}
+
+// groups a valid token stream (including COMMENT tokens) into rules, comments and blank lines.
+fn collect_items(tokens: &[Token]) -> Vec<Item> {
+  let mut items = vec![];
+  let mut last_line = 0;
+  let mut ndx = 0;
+
+  while ndx < tokens.len() && tokens[ndx].kind != "EOF" {
+    let token = &tokens[ndx];
+
+    if last_line != 0 && token.span.start.line_num > last_line + 1 {
+      items.push(Item::Blank);
+    }
+
+    if token.kind == "COMMENT" {
+      items.push(Item::Comment(token.value.trim_end().to_string()));
+      last_line = token.span.end.line_num;
+      ndx += 1;
+      continue;
+    }
+
+    let mut rule = Rule {
+      name: token.value.clone(),
+      alternatives: vec![Alternative::default()],
+      end_comments: vec![],
+      trailing_comment: None,
+    };
+
+    let mut prev = token;
+    let mut pending_comments: Vec<String> = vec![];
+    ndx += 1;
+
+    while ndx < tokens.len() && tokens[ndx].kind != "END" && tokens[ndx].kind != "EOF" {
+      let current = &tokens[ndx];
+      let alternative = rule.alternatives.last_mut().unwrap();
+
+      match current.kind.as_str() {
+        "COMMENT" => {
+          let text = current.value.trim_end().to_string();
+          let same_line = current.span.start.line_num == prev.span.end.line_num;
+
+          if same_line && !alternative.tokens.is_empty() && alternative.trailing_comment.is_none() {
+            alternative.trailing_comment = Some(text);
+          } else if alternative.tokens.is_empty() {
+            alternative.leading_comments.push(text);
+          } else {
+            pending_comments.push(text);
+          }
+        }
+        "|" => {
+          let mut next = Alternative::default();
+          next.leading_comments.append(&mut pending_comments);
+          rule.alternatives.push(next);
+        }
+        "EQUALS" => {}
+        _ => {
+          // a comment in the middle of an alternative moves in front of it.
+          alternative.leading_comments.append(&mut pending_comments);
+          alternative.tokens.push(current.value.clone());
+        }
+      }
+
+      prev = current;
+      ndx += 1;
+    }
+
+    rule.end_comments = pending_comments;
+    last_line = tokens[ndx].span.end.line_num;
+    ndx += 1;
+
+    if ndx < tokens.len() && tokens[ndx].kind == "COMMENT" && tokens[ndx].span.start.line_num == last_line {
+      rule.trailing_comment = Some(tokens[ndx].value.trim_end().to_string());
+      ndx += 1;
+    }
+
+    items.push(Item::Rule(rule));
+  }
+
+  items
+}
+
+fn group_rules(items: Vec<Item>) -> Vec<Item> {
+  let mut result: Vec<Item> = vec![];
+  let mut positions: HashMap<String, usize> = HashMap::new();
+
+  for item in items {
+    let Item::Rule(mut rule) = item else {
+      result.push(item);
+      continue;
+    };
+
+    let Some(position) = positions.get(&rule.name) else {
+      positions.insert(rule.name.clone(), result.len());
+      result.push(Item::Rule(rule));
+      continue;
+    };
+
+    let Item::Rule(first) = &mut result[*position] else {
+      unreachable!();
+    };
+
+    // keep both rules' comments attached to their alternatives.
+    if let Some(comment) = first.trailing_comment.take() {
+      let last = first.alternatives.last_mut().unwrap();
+
+      if last.trailing_comment.is_none() && first.end_comments.is_empty() {
+        last.trailing_comment = Some(comment);
+      } else {
+        first.end_comments.push(comment);
+      }
+    }
+
+    rule.alternatives[0].leading_comments.append(&mut first.end_comments);
+    if let Some(comment) = rule.trailing_comment.take() {
+      rule.end_comments.push(comment);
+    }
+
+    first.alternatives.append(&mut rule.alternatives);
+    first.end_comments = rule.end_comments;
+
+    // drop the blank line that separated the merged rule from its neighbours.
+    if let Some(Item::Blank) = result.last() {
+      result.pop();
+    }
+  }
+
+  result
+}
+
+fn rename(tokens: &[Token], style: NameStyle) -> Result<HashMap<String, String>, String> {
+  let mut defined: Vec<&String> = vec![];
+  for (ndx, token) in tokens.iter().enumerate() {
+    if token.kind == "ID" && tokens.get(ndx + 1).is_some_and(|x| x.kind == "EQUALS") && !defined.contains(&&token.value) {
+      defined.push(&token.value);
+    }
+  }
+
+  let mut mapping = HashMap::new();
+  for name in &defined {
+    let bare = name.trim_start_matches('<').trim_end_matches('>');
+    let renamed = match style {
+      NameStyle::Keep => name.to_string(),
+      NameStyle::Angle => format!("<{}>", bare),
+      NameStyle::Bare => bare.to_string(),
+    };
+
+    mapping.insert(name.to_string(), renamed);
+  }
+
+  // renaming must not merge two rules or turn a terminal into a rule.
+  let mut owners: HashMap<&String, &String> = HashMap::new();
+  for token in tokens.iter().filter(|x| x.kind == "ID") {
+    let renamed = mapping.get(&token.value).unwrap_or(&token.value);
+
+    match owners.get(renamed) {
+      Some(owner) if **owner != token.value => {
+        return Err(format!("`{}` and `{}` would both be written as `{}`", owner, token.value, renamed));
+      }
+      _ => {
+        owners.insert(renamed, &token.value);
+      }
+    }
+  }
+
+  Ok(mapping)
+}
+
+fn rename_items(items: &mut [Item], mapping: &HashMap<String, String>) {
+  for item in items {
+    let Item::Rule(rule) = item else {
+      continue;
+    };
+
+    if let Some(renamed) = mapping.get(&rule.name) {
+      rule.name = renamed.clone();
+    }
+
+    for alternative in &mut rule.alternatives {
+      for token in &mut alternative.tokens {
+        if let Some(renamed) = mapping.get(token) {
+          *token = renamed.clone();
+        }
+      }
+    }
+  }
+}
+
+fn inline_rule(rule: &Rule, options: &FormatOptions) -> String {
+  let mut words: Vec<&str> = vec![rule.name.as_str(), options.operator.as_str()];
+
+  for (ndx, alternative) in rule.alternatives.iter().enumerate() {
+    if ndx != 0 {
+      words.push("|");
+    }
+
+    words.extend(alternative.tokens.iter().map(|x| x.as_str()));
+  }
+
+  let mut result = words.join(" ");
+
+  // keep a space before the terminator of an empty alternative, `a ::= ;`.
+  if rule.alternatives.last().unwrap().tokens.is_empty() {
+    result.push(' ');
+  }
+
+  result.push_str(options.terminator.as_str());
+  result
+}
+
+fn aligned_rule(rule: &Rule, options: &FormatOptions) -> Vec<String> {
+  let mut lines = vec![];
+
+  let head = format!("{} {}", rule.name, options.operator.as_str());
+  let bar_indent = " ".repeat(head.chars().count() - 1);
+  let body_indent = " ".repeat(head.chars().count() + 1);
+  let last_ndx = rule.alternatives.len() - 1;
+  let terminate_last = rule.end_comments.is_empty();
+
+  for (ndx, alternative) in rule.alternatives.iter().enumerate() {
+    // comments of the first alternative go below the head, so they stay inside the rule when reformatted.
+    let comments_first = ndx == 0 && !alternative.leading_comments.is_empty();
+    if comments_first {
+      lines.push(head.clone());
+    }
+
+    for comment in &alternative.leading_comments {
+      lines.push(format!("{}{}", body_indent, comment));
+    }
+
+    if comments_first && alternative.tokens.is_empty() {
+      // an empty first alternative leaves nothing to print but a possible terminator.
+      if ndx == last_ndx && terminate_last {
+        lines.push(format!("{}{}", bar_indent, options.terminator.as_str()));
+      }
+
+      continue;
+    }
+
+    let mut line = if comments_first {
+      body_indent.clone()
+    } else if ndx == 0 {
+      head.clone()
+    } else {
+      format!("{}|", bar_indent)
+    };
+
+    if !alternative.tokens.is_empty() {
+      if !comments_first {
+        line.push(' ');
+      }
+
+      line.push_str(alternative.tokens.join(" ").as_str());
+    }
+
+    if ndx == last_ndx && terminate_last {
+      if alternative.tokens.is_empty() {
+        line.push(' ');
+      }
+
+      line.push_str(options.terminator.as_str());
+    }
+
+    if let Some(comment) = &alternative.trailing_comment {
+      line.push(' ');
+      line.push_str(comment.as_str());
+    }
+
+    lines.push(line);
+  }
+
+  if !terminate_last {
+    for comment in &rule.end_comments {
+      lines.push(format!("{}{}", body_indent, comment));
+    }
+
+    lines.push(format!("{}{}", bar_indent, options.terminator.as_str()));
+  }
+
+  lines
+}
+
+fn format_rule(rule: &Rule, options: &FormatOptions) -> Vec<String> {
+  let inline = inline_rule(rule, options);
+
+  let use_inline = match options.layout {
+    Layout::Inline => !rule.has_comments(),
+    Layout::Aligned => rule.alternatives.len() == 1 && !rule.has_comments(),
+    Layout::Auto => !rule.has_comments() && (rule.alternatives.len() == 1 || inline.chars().count() <= options.width),
+  };
+
+  let mut lines = if use_inline { vec![inline] } else { aligned_rule(rule, options) };
+
+  if let Some(comment) = &rule.trailing_comment {
+    let last = lines.last_mut().unwrap();
+    last.push(' ');
+    last.push_str(comment.as_str());
+  }
+
+  lines
+}
+
+/// Reprints a grammar in the canonical style, keeping its comments.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String, FormatError> {
+  let mut scanner = Scanner::with_comments(source.to_string());
+  let tokens = scanner.scan().map_err(|x| FormatError::Invalid(GrammarError { scan_errors: x, parse_errors: vec![] }))?;
+
+  let mut parser = Parser::new(tokens.iter().filter(|x| x.kind != "COMMENT").cloned().collect());
+  parser.parse().map_err(|x| FormatError::Invalid(GrammarError { scan_errors: vec![], parse_errors: x }))?;
+
+  format_grammar(&tokens, options).map_err(FormatError::NameClash)
+}
+
+// `tokens` must come from a comment preserving scan of a grammar that parses without errors.
+fn format_grammar(tokens: &[Token], options: &FormatOptions) -> Result<String, String> {
+  let mut items = collect_items(tokens);
+
+  if options.group_rules {
+    items = group_rules(items);
+  }
+
+  let mapping = rename(tokens, options.names)?;
+  rename_items(&mut items, &mapping);
+
+  let mut lines: Vec<String> = vec![];
+  for item in &items {
+    match item {
+      Item::Blank => {
+        if lines.last().is_some_and(|x| !x.is_empty()) {
+          lines.push(String::new());
+        }
+      }
+      Item::Comment(comment) => lines.push(comment.clone()),
+      Item::Rule(rule) => lines.extend(format_rule(rule, options)),
+    }
+  }
+
+  while lines.last().is_some_and(|x| x.is_empty()) {
+    lines.pop();
+  }
+
+  let mut result = lines.join("\n");
+  result.push('\n');
+  Ok(result)
+}
+
+impl Display for FormatError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FormatError::Invalid(error) => write!(f, "{}", error),
+      FormatError::NameClash(message) => write!(f, "{}", message),
+    }
+  }
+}
+
+impl Error for FormatError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const GRAMMARS: [&str; 6] = [
+    "// leading\n<a> ::= // first\n  'x' <b> | 'y' ; // trailing\n<b> ::= 'z' ;\n",
+    "<a> ::= // only comment\n  ;\n",
+    "<a> ::= | // second\n  'x' ;\n",
+    "<a> ::= 'x' // one\n  // two\n  | 'y'\n  // end\n  ;\n",
+    "<a> ::= 'x' // mid\n  'y' | ;\n\n\n// between\n<b> : 'q' .\n",
+    "<e> ::= <t> <e-rest> ;\n<e-rest> ::= '+' <t> <e-rest> | ;\n<t> ::= 'n' | '(' <e> ')' ; // done\n",
+  ];
+
+  fn format(source: &str, options: &FormatOptions) -> String {
+    super::format(source, options).unwrap()
+  }
+
+  fn options(layout: Layout, group_rules: bool) -> FormatOptions {
+    FormatOptions {
+      operator: Operator::Bnf,
+      terminator: Terminator::Semicolon,
+      layout,
+      width: 40,
+      names: NameStyle::Keep,
+      group_rules,
+    }
+  }
+
+  #[test]
+  fn formatting_is_idempotent() {
+    for layout in [Layout::Auto, Layout::Inline, Layout::Aligned] {
+      for group_rules in [false, true] {
+        let options = options(layout, group_rules);
+
+        for grammar in GRAMMARS {
+          let once = format(grammar, &options);
+          assert_eq!(format(&once, &options), once, "{:?} of {:?}", layout, grammar);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn first_alternative_keeps_its_comment() {
+    let formatted = format("<a> ::= // first\n  'x' | 'y' ;\n", &options(Layout::Auto, false));
+    assert_eq!(formatted, "<a> ::=\n        // first\n        'x'\n      | 'y';\n");
+  }
+}
@@ -0,0 +1,504 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Turns the analysed grammar into the values a language template is rendered with:
+//!
+//! * `file_stem`: file name of the main output without its extension, to refer to other outputs.
+//! * `options`: the language's options, like a package name.
+//! * `start`: name of the start rule.
+//! * `features`: whether each [`Feature`] is turned on, like `features.token_enum`.
+//! * `tokens`: every terminal kind the parser matches, including `EOF`.
+//! * `token_kinds`: the same kinds with a `name`, an `identifier` made of lowercase words joined by
+//!   `_`, like `left_paren` for `(`, and a `value` numbering them from 0. `EOF` comes first, as
+//!   `eof`.
+//! * `token_kinds_by_name`: the `token_kinds` entries sorted by name in byte order, for binary
+//!   searches.
+//! * `rules`: one entry per rule, in grammar order, with
+//!   * `name`: the rule name usable as an identifier, `grammar_name` as written in the grammar,
+//!   * `is_start` and `is_nullable`,
+//!   * `predict`: every token kind that can start the rule, the expected set on errors, and
+//!     `predict_kinds` with the same kinds as `token_kinds` entries,
+//!   * `alternatives`: one entry per production with its `predict` set and `predict_kinds`,
+//!     `dispatch` and `dispatch_kinds`, the predicted kinds no earlier alternative predicts, to
+//!     pick alternatives with a single `match` even in conflicting grammars, `is_empty` and
+//!     `symbols`, each symbol has a `name`, `is_terminal` and for terminals the `kind` identifier.
+//!
+//! Terminal names have their quotes removed, so templates decide how to quote them. Rule names and
+//! token kind identifiers the language reserves, or that clash with an earlier one as written,
+//! capitalized by `pascal` or uppercased, get `_` and then a number appended until they are free,
+//! so `match` becomes `match_` in Rust, `Expr` after `expr` becomes `Expr_2` and the token `self`
+//! becomes `self_2` rather than `TokenKind::Self`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::language::{CompiledLanguage, Feature, Language};
+use crate::productions::{NonTerminal, Production};
+use crate::template::{pascal_case, Template, TemplateError};
+
+// spaces per indentation level in templates.
+const TEMPLATE_INDENT_WIDTH: usize = 4;
+
+pub const DEFAULT_FILE_STEM: &str = "parser";
+
+pub struct GeneratedFile {
+  // appended to the output file stem, empty for the main output.
+  pub suffix: String,
+  pub source: String,
+}
+
+impl GeneratedFile {
+  /// Where this file goes when the main output is written to `main_path`.
+  pub fn path(&self, main_path: &Path) -> PathBuf {
+    if self.suffix.is_empty() {
+      return main_path.to_path_buf();
+    }
+
+    let stem = main_path.file_stem().unwrap_or_default().to_string_lossy();
+    main_path.with_file_name(format!("{}{}", stem, self.suffix))
+  }
+}
+
+// names of the characters terminals are usually made of, to name token kinds like `(`.
+const CHARACTER_NAMES: [(char, &str); 31] = [
+  ('(', "left_paren"), (')', "right_paren"), ('[', "left_bracket"), (']', "right_bracket"),
+  ('{', "left_brace"), ('}', "right_brace"), ('<', "less"), ('>', "greater"), ('=', "equals"),
+  ('+', "plus"), ('-', "minus"), ('*', "star"), ('/', "slash"), ('\\', "backslash"),
+  ('%', "percent"), ('^', "caret"), ('&', "ampersand"), ('|', "pipe"), ('!', "bang"),
+  ('?', "question"), ('~', "tilde"), ('.', "dot"), (',', "comma"), (':', "colon"),
+  (';', "semicolon"), ('#', "hash"), ('@', "at"), ('$', "dollar"), ('`', "backtick"),
+  ('\'', "quote"), ('"', "double_quote"),
+];
+
+#[derive(Serialize, Clone)]
+struct TokenKind {
+  name: String,
+  identifier: String,
+  value: usize,
+}
+
+#[derive(Serialize)]
+struct Symbol {
+  name: String,
+  is_terminal: bool,
+  kind: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Alternative {
+  predict: Vec<String>,
+  predict_kinds: Vec<TokenKind>,
+  dispatch: Vec<String>,
+  dispatch_kinds: Vec<TokenKind>,
+  is_empty: bool,
+  symbols: Vec<Symbol>,
+}
+
+#[derive(Serialize)]
+struct Rule {
+  name: String,
+  grammar_name: String,
+  is_start: bool,
+  is_nullable: bool,
+  predict: Vec<String>,
+  predict_kinds: Vec<TokenKind>,
+  alternatives: Vec<Alternative>,
+}
+
+#[derive(Serialize)]
+struct ParserModel<'a> {
+  file_stem: String,
+  options: &'a BTreeMap<String, String>,
+  features: BTreeMap<&'static str, bool>,
+  start: String,
+  tokens: Vec<String>,
+  token_kinds: Vec<TokenKind>,
+  token_kinds_by_name: Vec<TokenKind>,
+  rules: Vec<Rule>,
+}
+
+fn normalize_name(name: &str) -> String {
+  let mut result = String::new();
+
+  for character in name.chars() {
+    if character == '<' || character == '>' {
+      continue;
+    }
+
+    if character == '-' {
+      result.push('_');
+      continue;
+    }
+
+    result.push(character);
+  }
+
+  result
+}
+
+// the ways templates print an identifier: as it is, through the `pascal` filter and through `upper`.
+fn spellings(identifier: &str) -> [String; 3] {
+  [identifier.to_string(), pascal_case(identifier), identifier.to_uppercase()]
+}
+
+fn spellings_clash(first: &[String], second: &[String]) -> bool {
+  first.iter().zip(second).any(|(a, b)| a == b)
+}
+
+// hands out identifiers that differ from each other and from the reserved words in every spelling
+// the language writes them in.
+struct Identifiers<'a> {
+  reserved: &'a BTreeSet<String>,
+  spellings: &'a [Template],
+  taken: Vec<Vec<String>>,
+}
+
+impl<'a> Identifiers<'a> {
+  fn new(reserved: &'a BTreeSet<String>, spellings: &'a [Template]) -> Self {
+    Identifiers { reserved, spellings, taken: vec![] }
+  }
+
+  // `Language::compile` checked that the spellings render.
+  fn spell(&self, identifier: &str) -> Vec<String> {
+    let data = serde_json::json!({ "name": identifier });
+    self.spellings.iter().map(|x| x.render(&data, &BTreeMap::new()).unwrap()).collect()
+  }
+
+  fn is_free(&self, identifier: &str) -> bool {
+    let spellings = self.spell(identifier);
+    !spellings.iter().any(|x| self.reserved.contains(x)) && !self.taken.iter().any(|x| spellings_clash(x, &spellings))
+  }
+
+  // the first free one of `base`, `base_`, `base_2`, `base_3` and so on.
+  fn claim(&mut self, base: &str) -> String {
+    let mut identifier = base.to_string();
+    let mut attempt = 0;
+
+    while !self.is_free(&identifier) {
+      attempt += 1;
+      identifier = if attempt == 1 { format!("{}_", base) } else { format!("{}_{}", base, attempt) };
+    }
+
+    self.taken.push(self.spell(&identifier));
+    identifier
+  }
+}
+
+// identifiers of every rule by grammar name, in grammar order so renaming is deterministic.
+fn rule_identifiers(non_terminals: &[NonTerminal], language: &Language, compiled: &CompiledLanguage) -> BTreeMap<String, String> {
+  let mut identifiers = Identifiers::new(&language.reserved, &compiled.rule_spellings);
+  non_terminals.iter().map(|x| (x.name.clone(), identifiers.claim(&normalize_name(&x.name)))).collect()
+}
+
+/// Pairs of rules whose identifiers clash however a template prints them, like `a-b` and `a_b`,
+/// or `expr` and `Expr` once capitalized. The earlier rule comes first, the later one is renamed in
+/// generated parsers.
+pub(crate) fn name_collisions(non_terminals: &[NonTerminal]) -> Vec<(&NonTerminal, &NonTerminal)> {
+  let mut seen: Vec<(&NonTerminal, [String; 3])> = vec![];
+  let mut result = vec![];
+
+  for nt in non_terminals {
+    let spellings = spellings(&normalize_name(&nt.name));
+
+    match seen.iter().find(|x| spellings_clash(&x.1, &spellings)) {
+      Some((first, _)) => result.push((*first, nt)),
+      None => seen.push((nt, spellings)),
+    }
+  }
+
+  result
+}
+
+fn strip_literal(literal: &str) -> String {
+  literal.replace("'", "").replace("\"", "")
+}
+
+// the empty string stands for the end of input in predict sets.
+fn token_name(token: &str) -> String {
+  if token.is_empty() {
+    "EOF".to_string()
+  } else {
+    strip_literal(token)
+  }
+}
+
+fn predict_list(predict_set: &BTreeSet<String>) -> Vec<String> {
+  let names: BTreeSet<String> = predict_set.iter().map(|x| token_name(x)).collect();
+  names.into_iter().collect()
+}
+
+// `NUM` becomes `num` and `->` becomes `minus_greater`.
+fn token_identifier(name: &str) -> String {
+  let mut words: Vec<String> = vec![];
+  let mut in_word = false;
+
+  for character in name.chars() {
+    if character.is_alphanumeric() {
+      if !in_word {
+        words.push(String::new());
+        in_word = true;
+      }
+
+      words.last_mut().unwrap().extend(character.to_lowercase());
+      continue;
+    }
+
+    in_word = false;
+    match CHARACTER_NAMES.iter().find(|x| x.0 == character) {
+      Some((_, word)) => words.push(word.to_string()),
+      None if character == '_' || character.is_whitespace() => {}
+      None => words.push(format!("u{:04x}", character as u32)),
+    }
+  }
+
+  let identifier = words.join("_");
+  if identifier.is_empty() || identifier.starts_with(|x: char| x.is_numeric()) {
+    format!("token_{}", identifier)
+  } else {
+    identifier
+  }
+}
+
+// token kinds by name. Identifiers the language reserves or that clash, like those of `a-b` and
+// `a_minus_b`, are renamed like rules.
+fn token_kinds(tokens: &BTreeSet<String>, language: &Language, compiled: &CompiledLanguage) -> BTreeMap<String, TokenKind> {
+  let mut identifiers = Identifiers::new(&language.reserved, &compiled.token_spellings);
+  let mut result = BTreeMap::new();
+  let eof = "EOF".to_string();
+
+  for (value, name) in [&eof].into_iter().chain(tokens.iter().filter(|x| **x != eof)).enumerate() {
+    let identifier = identifiers.claim(&token_identifier(name));
+    result.insert(name.clone(), TokenKind { name: name.clone(), identifier, value });
+  }
+
+  result
+}
+
+fn build_alternative(prod: &Production, identifiers: &BTreeMap<String, String>) -> Alternative {
+  let mut symbols = vec![];
+
+  for token in &prod.list {
+    let symbol = match token.kind.as_str() {
+      "TERM" => Symbol { name: strip_literal(&token.value), is_terminal: true, kind: None },
+      "ID" => Symbol { name: identifiers[&token.value].clone(), is_terminal: false, kind: None },
+      "EOF" => Symbol { name: "EOF".to_string(), is_terminal: true, kind: None },
+      _ => continue,
+    };
+
+    symbols.push(symbol);
+  }
+
+  Alternative {
+    predict: predict_list(&prod.predict_set),
+    predict_kinds: vec![],
+    dispatch: vec![],
+    dispatch_kinds: vec![],
+    is_empty: symbols.is_empty(),
+    symbols,
+  }
+}
+
+// fills in the token kinds once every terminal is known.
+fn add_token_kinds(rule: &mut Rule, kinds: &BTreeMap<String, TokenKind>) {
+  rule.predict_kinds = rule.predict.iter().map(|x| kinds[x].clone()).collect();
+
+  for alternative in &mut rule.alternatives {
+    alternative.predict_kinds = alternative.predict.iter().map(|x| kinds[x].clone()).collect();
+    alternative.dispatch_kinds = alternative.dispatch.iter().map(|x| kinds[x].clone()).collect();
+
+    for symbol in alternative.symbols.iter_mut().filter(|x| x.is_terminal) {
+      symbol.kind = Some(kinds[&symbol.name].identifier.clone());
+    }
+  }
+}
+
+fn build_model<'a>(non_terminals: &[NonTerminal], language: &'a Language, compiled: &CompiledLanguage, file_stem: &str) -> ParserModel<'a> {
+  let start_term = non_terminals.iter().find(|x| x.is_start_term).unwrap();
+  let identifiers = rule_identifiers(non_terminals, language, compiled);
+  let mut tokens = BTreeSet::from(["EOF".to_string()]);
+
+  let mut rules: Vec<Rule> = non_terminals.iter().map(|nt| {
+    let mut alternatives: Vec<Alternative> = nt.productions.iter().map(|x| build_alternative(x, &identifiers)).collect();
+
+    // in conflicting grammars a token selects the first alternative it predicts.
+    let mut claimed = BTreeSet::new();
+    for alternative in &mut alternatives {
+      tokens.extend(alternative.symbols.iter().filter(|x| x.is_terminal).map(|x| x.name.clone()));
+      alternative.dispatch = alternative.predict.iter().filter(|x| claimed.insert(x.to_string())).cloned().collect();
+    }
+
+    Rule {
+      name: identifiers[&nt.name].clone(),
+      grammar_name: nt.name.clone(),
+      is_start: nt.is_start_term,
+      is_nullable: nt.is_nullable,
+      predict: predict_list(&nt.predict_set),
+      predict_kinds: vec![],
+      alternatives,
+    }
+  }).collect();
+
+  let kinds = token_kinds(&tokens, language, compiled);
+  for rule in &mut rules {
+    add_token_kinds(rule, &kinds);
+  }
+
+  let token_kinds_by_name: Vec<TokenKind> = kinds.into_values().collect();
+  let mut token_kinds = token_kinds_by_name.clone();
+  token_kinds.sort_by_key(|x| x.value);
+
+  ParserModel {
+    file_stem: file_stem.to_string(),
+    options: &language.options,
+    features: Feature::ALL.iter().map(|x| (x.as_str(), language.enabled.contains(x))).collect(),
+    start: identifiers[&start_term.name].clone(),
+    tokens: tokens.into_iter().collect(),
+    token_kinds,
+    token_kinds_by_name,
+    rules,
+  }
+}
+
+// rewrites a line's template indentation, four spaces or a tab per level, with `unit`. Spaces
+// that do not make up a full level are kept for alignment.
+fn reindent(line: &str, unit: &str) -> String {
+  let mut levels = 0;
+  let mut spaces = 0;
+  let mut rest = line;
+
+  while let Some(character) = rest.chars().next() {
+    match character {
+      '\t' => {
+        levels += 1;
+        spaces = 0;
+      }
+      ' ' if spaces == TEMPLATE_INDENT_WIDTH - 1 => {
+        levels += 1;
+        spaces = 0;
+      }
+      ' ' => spaces += 1,
+      _ => break,
+    }
+
+    rest = &rest[1..];
+  }
+
+  format!("{}{}{}", unit.repeat(levels), " ".repeat(spaces), rest)
+}
+
+fn finish_output(output: String, language: &Language) -> String {
+  let unit = language.indent.unit();
+  let line_ending = language.line_ending.as_str();
+
+  // values that render empty would otherwise leave indentation behind.
+  output.lines().map(|x| format!("{}{}", reindent(x.trim_end(), unit.as_str()), line_ending)).collect()
+}
+
+/// Generates every file of the parser, the main output first with an empty suffix. `file_stem`
+/// is the main output's file name without its extension.
+pub fn generate_files(non_terminals: &[NonTerminal], language: &Language, file_stem: &str) -> Result<Vec<GeneratedFile>, TemplateError> {
+  let compiled = language.compile()?;
+  let model = serde_json::to_value(build_model(non_terminals, language, &compiled, file_stem)).unwrap();
+
+  let mut result = vec![GeneratedFile {
+    suffix: String::new(),
+    source: finish_output(compiled.template.render(&model, &compiled.partials)?, language),
+  }];
+
+  for (suffix, template) in &compiled.files {
+    result.push(GeneratedFile {
+      suffix: suffix.clone(),
+      source: finish_output(template.render(&model, &compiled.partials)?, language),
+    });
+  }
+
+  Ok(result)
+}
+
+/// Generates the main output file of the parser, named `parser.<extension>`.
+pub fn generate_parser(non_terminals: &[NonTerminal], language: &Language) -> Result<String, TemplateError> {
+  let compiled = language.compile()?;
+  let model = serde_json::to_value(build_model(non_terminals, language, &compiled, DEFAULT_FILE_STEM)).unwrap();
+  Ok(finish_output(compiled.template.render(&model, &compiled.partials)?, language))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::grammar::Grammar;
+
+  fn identifiers(source: &str, language: &Language) -> Vec<String> {
+    let grammar = Grammar::parse(source).unwrap();
+    let identifiers = rule_identifiers(grammar.non_terminals(), language, &language.compile().unwrap());
+    grammar.non_terminals().iter().map(|x| identifiers[&x.name].clone()).collect()
+  }
+
+  #[test]
+  fn normalizes_rule_names() {
+    assert_eq!(identifiers("<expr> ::= <expr-rest> ; <expr-rest> ::= ;", &Language::rust()), ["expr", "expr_rest"]);
+  }
+
+  #[test]
+  fn renames_reserved_rules() {
+    assert_eq!(identifiers("type ::= 'x' match ; match ::= ;", &Language::rust()), ["type_", "match_"]);
+  }
+
+  #[test]
+  fn renames_colliding_rules() {
+    assert_eq!(identifiers("a-b ::= a_b ; a_b ::= <a-b_> ; <a-b_> ::= ;", &Language::rust()), ["a_b", "a_b_", "a_b__"]);
+  }
+
+  #[test]
+  fn renames_rules_that_clash_once_lowercased() {
+    assert_eq!(identifiers("expr ::= Expr ; Expr ::= EXPR ; EXPR ::= ;", &Language::rust()), ["expr", "Expr_", "EXPR_2"]);
+  }
+
+  #[test]
+  fn renames_rules_whose_spelling_is_reserved() {
+    assert_eq!(identifiers("Self ::= ;", &Language::rust()), ["Self_"]);
+    assert_eq!(identifiers("current ::= if ; if ::= ;", &Language::builtin("python").unwrap()), ["current_", "if"]);
+  }
+
+  fn kind_identifiers(names: &[&str], language: &Language) -> Vec<String> {
+    let tokens = names.iter().map(|x| x.to_string()).collect();
+    let kinds = token_kinds(&tokens, language, &language.compile().unwrap());
+    names.iter().map(|x| kinds[*x].identifier.clone()).collect()
+  }
+
+  #[test]
+  fn renames_reserved_token_kinds() {
+    assert_eq!(kind_identifiers(&["EOF", "self", "match", "+"], &Language::rust()), ["eof", "self_2", "match", "plus"]);
+    assert_eq!(kind_identifiers(&["if", "kind_count"], &Language::builtin("c").unwrap()), ["if", "kind_count_"]);
+  }
+
+  #[test]
+  fn dispatches_shared_tokens_to_the_first_alternative() {
+    let grammar = Grammar::parse("call ::= ID '(' ')' | ID | NUM | ;").unwrap();
+    let language = Language::rust();
+    let model = build_model(grammar.non_terminals(), &language, &language.compile().unwrap(), DEFAULT_FILE_STEM);
+    let dispatch: Vec<Vec<&str>> = model.rules[0].alternatives.iter().map(|x| x.dispatch.iter().map(String::as_str).collect()).collect();
+    assert_eq!(dispatch, [vec!["ID"], vec![], vec!["NUM"], vec!["EOF"]]);
+  }
+
+  #[test]
+  fn reports_collisions_of_any_spelling() {
+    let grammar = Grammar::parse("expr ::= Expr a-b ; Expr ::= a_b ; a-b ::= ; a_b ::= ; ab ::= ;").unwrap();
+    let pairs: Vec<(&str, &str)> = name_collisions(grammar.non_terminals()).iter().map(|(a, b)| (a.name.as_str(), b.name.as_str())).collect();
+    assert_eq!(pairs, [("expr", "Expr"), ("a-b", "a_b")]);
+  }
+}
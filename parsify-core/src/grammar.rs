@@ -0,0 +1,182 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::diagnostic::{Diagnostic, Renderer};
+use crate::error_handler::{ambiguity_diagnostic, name_collision_diagnostic, parse_error_diagnostic, scan_error_diagnostic, undefined_symbol_diagnostic};
+use crate::generator::name_collisions;
+use crate::parser::{Parser, ParserError};
+use crate::productions::{self, Ambiguity, NonTerminal};
+use crate::report::{generate_report, ReportFormat};
+use crate::scanner::{Coord, ScanError, Scanner, Token};
+
+/// A parsed and analyzed grammar.
+#[derive(Debug, Clone)]
+pub struct Grammar {
+  non_terminals: Vec<NonTerminal>,
+  conflicts: Vec<Ambiguity>,
+  undefined_symbols: Vec<Token>,
+}
+
+/// Every scan and syntax error of a grammar, scanning recovers so both are reported together.
+#[derive(Debug)]
+pub struct GrammarError {
+  pub(crate) scan_errors: Vec<ScanError>,
+  pub(crate) parse_errors: Vec<ParserError>,
+}
+
+impl Grammar {
+  /// Scans, parses and analyzes a grammar. Conflicts are not errors, see [`Grammar::conflicts`].
+  pub fn parse(source: &str) -> Result<Grammar, GrammarError> {
+    let mut scanner = Scanner::new(source.to_string());
+    let (tokens, scan_errors) = match scanner.scan() {
+      Ok(tokens) => (tokens, vec![]),
+      Err(errors) => (scanner.tokens().clone(), errors),
+    };
+
+    let mut parser = Parser::new(tokens.clone());
+    let parsed = parser.parse();
+
+    let mut non_terminals = match parsed {
+      Ok(non_terminals) if scan_errors.is_empty() => non_terminals,
+      Ok(_) => return Err(GrammarError { scan_errors, parse_errors: vec![] }),
+      Err(parse_errors) => {
+        let parse_errors = without_follow_on_errors(parse_errors, &scan_errors, &tokens);
+        return Err(GrammarError { scan_errors, parse_errors });
+      }
+    };
+    let conflicts = productions::process(&mut non_terminals);
+
+    Ok(Grammar {
+      non_terminals,
+      conflicts,
+      undefined_symbols: parser.undefined_symbols().clone(),
+    })
+  }
+
+  /// The rules in the order they are first defined, with their nullable, FIRST, FOLLOW and
+  /// predict sets.
+  pub fn non_terminals(&self) -> &Vec<NonTerminal> {
+    &self.non_terminals
+  }
+
+  /// Name of the start rule, the first one defined. `None` for a grammar without rules.
+  pub fn start(&self) -> Option<&str> {
+    self.non_terminals.iter().find(|x| x.is_start_term).map(|x| x.name.as_str())
+  }
+
+  /// Alternatives of the same rule whose predict sets overlap.
+  pub fn conflicts(&self) -> &Vec<Ambiguity> {
+    &self.conflicts
+  }
+
+  /// `<name>` identifiers used without a rule, they are matched as terminals.
+  pub fn undefined_symbols(&self) -> &Vec<Token> {
+    &self.undefined_symbols
+  }
+
+  pub fn warnings(&self) -> Vec<Diagnostic> {
+    let mut result: Vec<Diagnostic> = self.undefined_symbols.iter().map(undefined_symbol_diagnostic).collect();
+    result.extend(self.conflicts.iter().map(ambiguity_diagnostic));
+    result.extend(name_collisions(&self.non_terminals).into_iter().map(|(first, second)| name_collision_diagnostic(first, second)));
+    result
+  }
+
+  pub fn report(&self, format: ReportFormat) -> String {
+    generate_report(&self.non_terminals, format)
+  }
+}
+
+// the scanner skips the rest of a line with a bad character, often taking a `;` with it, so the
+// parser then fails somewhere between that line and the end of the next rule.
+pub(crate) fn without_follow_on_errors(parse_errors: Vec<ParserError>, scan_errors: &[ScanError], tokens: &[Token]) -> Vec<ParserError> {
+  let mut follow_on: Vec<Coord> = vec![];
+
+  for line_num in scan_errors.iter().map(|x| x.coord().line_num) {
+    follow_on.extend(tokens.iter().filter(|x| x.span.start.line_num == line_num).map(|x| x.span.start));
+
+    let rule_ended = tokens.iter().rev().find(|x| x.span.start.line_num <= line_num).is_some_and(|x| x.kind == "END");
+    if rule_ended {
+      continue;
+    }
+
+    for token in tokens.iter().filter(|x| x.span.start.line_num > line_num) {
+      follow_on.push(token.span.start);
+      if token.kind == "END" {
+        break;
+      }
+    }
+  }
+
+  parse_errors.into_iter().filter(|ParserError::UnexpectedToken(token, _)| {
+    !follow_on.iter().any(|x| x.line_num == token.span.start.line_num && x.col == token.span.start.col)
+  }).collect()
+}
+
+impl GrammarError {
+  pub fn diagnostics(&self) -> Vec<Diagnostic> {
+    let mut result: Vec<Diagnostic> = self.scan_errors.iter().map(scan_error_diagnostic).collect();
+    result.extend(self.parse_errors.iter().map(parse_error_diagnostic));
+    result
+  }
+
+  /// Renders every error as an annotated snippet of `source`.
+  pub fn render(&self, file_path: &str, source: &str, color: bool) -> String {
+    let renderer = Renderer::new(file_path.to_string(), source, color);
+    self.diagnostics().iter().map(|x| renderer.render(x)).collect::<Vec<String>>().join("\n")
+  }
+}
+
+impl Display for GrammarError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let mut messages: Vec<String> = self.scan_errors.iter().map(|x| x.to_string()).collect();
+    messages.extend(self.parse_errors.iter().map(|x| x.to_string()));
+
+    write!(f, "{}", messages.join("\n"))
+  }
+}
+
+impl Error for GrammarError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn error_lines(source: &str) -> (Vec<usize>, Vec<usize>) {
+    let error = Grammar::parse(source).unwrap_err();
+    let scan = error.scan_errors.iter().map(|x| x.coord().line_num).collect();
+    let parse = error.parse_errors.iter().map(|ParserError::UnexpectedToken(token, _)| token.span.start.line_num).collect();
+    (scan, parse)
+  }
+
+  #[test]
+  fn skips_the_rest_of_a_line_after_a_scan_error() {
+    assert_eq!(error_lines("a ::= 'x' $ 'y' ;\nb ::= 'z' ;\n"), (vec![1], vec![]));
+  }
+
+  #[test]
+  fn drops_syntax_errors_caused_by_the_skipped_line() {
+    assert_eq!(error_lines("a ::= 'x;\nb ::= 'y' ;\n"), (vec![1], vec![]));
+  }
+
+  #[test]
+  fn reports_syntax_errors_after_the_next_rule() {
+    assert_eq!(error_lines("a ::= 'x;\nb ::= 'y' ;\nc ::= ::= ;\n"), (vec![1], vec![3]));
+  }
+}
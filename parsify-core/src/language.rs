@@ -0,0 +1,616 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
+use serde::de::{Error as _, Unexpected};
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::diagnostic::{Diagnostic, Label, Renderer, Severity};
+use crate::scanner::{Coord, Span};
+use crate::template::{Template, TemplateError};
+
+// language specs compiled into parsify, user specs with the same name take precedence.
+const BUILTIN_LANGUAGES: [(&str, &str); 13] = [
+  ("c", include_str!("../langs/c.json")),
+  ("cpp", include_str!("../langs/cpp.json")),
+  ("csharp", include_str!("../langs/csharp.json")),
+  ("go", include_str!("../langs/go.json")),
+  ("haskell", include_str!("../langs/haskell.json")),
+  ("java", include_str!("../langs/java.json")),
+  ("javascript", include_str!("../langs/javascript.json")),
+  ("kotlin", include_str!("../langs/kotlin.json")),
+  ("lua", include_str!("../langs/lua.json")),
+  ("ocaml", include_str!("../langs/ocaml.json")),
+  ("python", include_str!("../langs/python.json")),
+  ("rust", include_str!("../langs/rust.json")),
+  ("typescript", include_str!("../langs/typescript.json")),
+];
+
+// colon (or semicolon on windows) separated list of directories holding `<name>.json` specs.
+const LANG_PATH_VAR: &str = "PARSIFY_LANG_PATH";
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+  #[default]
+  Spaces,
+  Tabs,
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+  #[default]
+  Lf,
+  Crlf,
+}
+
+/// Optional ways of generating parsers. Specs list the ones their templates implement and users
+/// turn them on with `--feature`.
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+  // token kinds are an enum or constants instead of strings.
+  TokenEnum,
+  // rules dispatch with a single match or switch on the current token kind.
+  MatchDispatch,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Indent {
+  pub style: IndentStyle,
+  // spaces per level, ignored for tabs.
+  #[serde(deserialize_with = "indent_width")]
+  pub width: usize,
+}
+
+/// How templates write rule and token kind identifiers in code, each a template printing the
+/// identifier `name`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spellings {
+  pub rules: Vec<String>,
+  pub tokens: Vec<String>,
+}
+
+/// A target language, described by the template the parser is rendered from. The README describes
+/// the template syntax and the values templates can use.
+///
+/// Templates indent with four spaces or one tab per level, the generated code is reindented with
+/// `indent` and uses `line_ending`.
+///
+/// `template` renders the main output file. Languages that need more files, like C headers, list
+/// a template for each of them in `files`, keyed by the suffix added to the output's file stem.
+/// Languages that tie the file name to a declaration, like a Java class, render it from `options`
+/// with the `file_stem` template.
+/// `options` are settings like a package name, with their default values, that templates read as
+/// `options.<name>` and users can override. Rules and token kinds are renamed when one of their
+/// `spellings` is `reserved`, like a keyword or the name of a helper the template defines, or is
+/// already taken.
+/// `features` are the optional generation modes the templates implement, see [`Language::enable`].
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Language {
+  // of the main output file, without the dot.
+  #[serde(default)]
+  pub extension: String,
+  // template for the main output's file stem, for languages that tie it to a declaration.
+  #[serde(default)]
+  pub file_stem: Option<String>,
+  pub template: Vec<String>,
+  #[serde(default)]
+  pub files: BTreeMap<String, Vec<String>>,
+  #[serde(default)]
+  pub partials: BTreeMap<String, Vec<String>>,
+  #[serde(default)]
+  pub options: BTreeMap<String, String>,
+  #[serde(default)]
+  pub reserved: BTreeSet<String>,
+  #[serde(default)]
+  pub spellings: Spellings,
+  #[serde(default)]
+  pub features: BTreeSet<Feature>,
+  // features turned on for the next generation.
+  #[serde(skip)]
+  pub enabled: BTreeSet<Feature>,
+  #[serde(default)]
+  pub indent: Indent,
+  #[serde(default)]
+  pub line_ending: LineEnding,
+}
+
+impl Feature {
+  pub const ALL: [Feature; 2] = [Feature::TokenEnum, Feature::MatchDispatch];
+
+  /// The name specs and templates use, like `match_dispatch`.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Feature::TokenEnum => "token_enum",
+      Feature::MatchDispatch => "match_dispatch",
+    }
+  }
+
+  /// The name `--feature` takes, like `match-dispatch`.
+  pub fn flag_name(&self) -> &'static str {
+    match self {
+      Feature::TokenEnum => "token-enum",
+      Feature::MatchDispatch => "match-dispatch",
+    }
+  }
+}
+
+impl Default for Spellings {
+  // as it is, through the `pascal` filter and through `upper`.
+  fn default() -> Self {
+    let spellings = ["{{name}}", "{{name | pascal}}", "{{name | upper}}"].map(String::from).to_vec();
+    Spellings { rules: spellings.clone(), tokens: spellings }
+  }
+}
+
+impl Default for Indent {
+  fn default() -> Self {
+    Indent { style: IndentStyle::Spaces, width: 4 }
+  }
+}
+
+impl Indent {
+  pub fn unit(&self) -> String {
+    match self.style {
+      IndentStyle::Spaces => " ".repeat(self.width),
+      IndentStyle::Tabs => "\t".to_string(),
+    }
+  }
+}
+
+impl LineEnding {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      LineEnding::Lf => "\n",
+      LineEnding::Crlf => "\r\n",
+    }
+  }
+}
+
+/// The parsed templates of a [`Language`].
+pub struct CompiledLanguage {
+  pub template: Template,
+  // suffix and template of every additional output file.
+  pub files: Vec<(String, Template)>,
+  pub partials: BTreeMap<String, Template>,
+  pub rule_spellings: Vec<Template>,
+  pub token_spellings: Vec<Template>,
+}
+
+/// A spec that is not valid JSON or does not match the shape of [`Language`].
+#[derive(Debug, Clone)]
+pub struct SpecError {
+  pub code: &'static str,
+  // dotted path of the offending field, empty when the JSON itself is malformed.
+  pub field: String,
+  pub message: String,
+  pub at: Coord,
+}
+
+#[derive(Debug)]
+pub enum LanguageError {
+  NotFound(String, Vec<PathBuf>),
+  Io(PathBuf, io::Error),
+  // path, spec source and what is wrong with it.
+  Invalid(PathBuf, String, Box<SpecError>),
+}
+
+/// Where an [`AvailableLanguage`] is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LanguageLocation {
+  Builtin,
+  File(PathBuf),
+}
+
+/// A language spec that `Language::find` can load by name.
+#[derive(Debug, Clone)]
+pub struct AvailableLanguage {
+  pub name: String,
+  pub location: LanguageLocation,
+}
+
+impl Language {
+  pub fn from_json(json: &str) -> Result<Language, SpecError> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+
+    let language: Language = serde_path_to_error::deserialize(&mut deserializer).map_err(|x| {
+      let field = x.path().to_string();
+      SpecError::new(if field == "." { String::new() } else { field }, x.into_inner())
+    })?;
+
+    deserializer.end().map_err(|x| SpecError::new(String::new(), x))?;
+    language.compile().map_err(|x| SpecError::template(json, &language, x))?;
+    language.output_file_stem().map_err(|x| SpecError::template(json, &language, x))?;
+    Ok(language)
+  }
+
+  /// Parses the templates and partials, checking that every included partial exists.
+  pub(crate) fn compile(&self) -> Result<CompiledLanguage, TemplateError> {
+    let template = Template::parse("template", join_lines(&self.template).as_str())?;
+
+    let mut files = vec![];
+    for (suffix, lines) in &self.files {
+      files.push((suffix.clone(), Template::parse(format!("files.{}", suffix).as_str(), join_lines(lines).as_str())?));
+    }
+
+    let mut partials = BTreeMap::new();
+    for (name, lines) in &self.partials {
+      let partial = Template::parse(format!("partials.{}", name).as_str(), join_lines(lines).as_str())?;
+      partials.insert(name.clone(), partial);
+    }
+
+    let all = std::iter::once(&template).chain(files.iter().map(|x| &x.1)).chain(partials.values());
+    for compiled in all {
+      compiled.check_partials(|x| self.partials.contains_key(x))?;
+    }
+
+    let rule_spellings = compile_spellings("rules", &self.spellings.rules)?;
+    let token_spellings = compile_spellings("tokens", &self.spellings.tokens)?;
+
+    Ok(CompiledLanguage { template, files, partials, rule_spellings, token_spellings })
+  }
+
+  /// The file stem `file_stem` renders with the current options, if the spec has one.
+  pub fn output_file_stem(&self) -> Result<Option<String>, TemplateError> {
+    let Some(file_stem) = &self.file_stem else {
+      return Ok(None);
+    };
+
+    let template = Template::parse("file_stem", file_stem)?;
+    template.render(&serde_json::json!({ "options": self.options }), &BTreeMap::new()).map(Some)
+  }
+
+  /// Overrides the value of an option the spec defines.
+  pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+    if self.options.is_empty() {
+      return Err(format!("unknown option `{}`, the language has no options", name));
+    }
+
+    if !self.options.contains_key(name) {
+      let names: Vec<String> = self.options.keys().map(|x| format!("`{}`", x)).collect();
+      return Err(format!("unknown option `{}`, expected one of {}", name, names.join(", ")));
+    }
+
+    self.options.insert(name.to_string(), value.to_string());
+    Ok(())
+  }
+
+  /// Turns on a feature the spec implements.
+  pub fn enable(&mut self, feature: Feature) -> Result<(), String> {
+    if !self.features.contains(&feature) {
+      return Err(format!("the language does not support the `{}` feature", feature.flag_name()));
+    }
+
+    self.enabled.insert(feature);
+    Ok(())
+  }
+
+  // the spec key and lines of the template an error refers to.
+  fn template_source<'a>(&'a self, name: &'a str) -> (&'a str, &'a [String]) {
+    if let Some(partial) = name.strip_prefix("partials.") {
+      (partial, &self.partials[partial])
+    } else if let Some(suffix) = name.strip_prefix("files.") {
+      (suffix, &self.files[suffix])
+    } else if let (Some(file_stem), "file_stem") = (&self.file_stem, name) {
+      ("file_stem", std::slice::from_ref(file_stem))
+    } else {
+      ("template", &self.template)
+    }
+  }
+
+  pub fn from_file(path: &Path) -> Result<Language, LanguageError> {
+    let json = fs::read_to_string(path).map_err(|x| LanguageError::Io(path.to_path_buf(), x))?;
+    Language::from_json(json.as_str()).map_err(|x| LanguageError::Invalid(path.to_path_buf(), json, Box::new(x)))
+  }
+
+  pub fn builtin(name: &str) -> Option<Language> {
+    let (_, json) = BUILTIN_LANGUAGES.iter().find(|x| x.0 == name)?;
+    Some(Language::from_json(json).unwrap())
+  }
+
+  /// The bundled Rust language spec.
+  pub fn rust() -> Language {
+    Language::builtin("rust").unwrap()
+  }
+
+  /// Loads `name_or_path` as a spec file if it looks like a path, otherwise looks for
+  /// `<name>.json` in the search paths before falling back to the built-in specs.
+  pub fn find(name_or_path: &str) -> Result<Language, LanguageError> {
+    let as_path = Path::new(name_or_path);
+    if name_or_path.ends_with(".json") || as_path.components().count() > 1 {
+      return Language::from_file(as_path);
+    }
+
+    let search_paths = search_paths();
+    for dir in &search_paths {
+      let candidate = dir.join(format!("{}.json", name_or_path));
+      if candidate.is_file() {
+        return Language::from_file(&candidate);
+      }
+    }
+
+    Language::builtin(name_or_path).ok_or(LanguageError::NotFound(name_or_path.to_string(), search_paths))
+  }
+
+  /// Every language `find` can load by name, in lookup order and without duplicates.
+  pub fn available() -> Vec<AvailableLanguage> {
+    let mut result: Vec<AvailableLanguage> = vec![];
+
+    for dir in search_paths() {
+      let Ok(entries) = fs::read_dir(&dir) else {
+        continue;
+      };
+
+      let mut paths: Vec<PathBuf> = entries.filter_map(|x| x.ok()).map(|x| x.path()).collect();
+      paths.sort();
+
+      for path in paths {
+        if path.extension().is_none_or(|x| x != "json") {
+          continue;
+        }
+
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        if !result.iter().any(|x| x.name == name) {
+          result.push(AvailableLanguage { name, location: LanguageLocation::File(path) });
+        }
+      }
+    }
+
+    for (name, _) in BUILTIN_LANGUAGES {
+      if !result.iter().any(|x| x.name == name) {
+        result.push(AvailableLanguage { name: name.to_string(), location: LanguageLocation::Builtin });
+      }
+    }
+
+    result
+  }
+}
+
+impl SpecError {
+  fn new(field: String, error: serde_json::Error) -> Self {
+    let message = error.to_string();
+
+    // serde_json appends the location, which is reported separately.
+    let message = match message.rfind(" at line ") {
+      Some(ndx) => message[..ndx].to_string(),
+      None => message,
+    };
+
+    let code = if error.is_syntax() || error.is_eof() {
+      "L0001"
+    } else if message.starts_with("missing field") {
+      "L0002"
+    } else if message.starts_with("unknown field") {
+      "L0004"
+    } else {
+      "L0003"
+    };
+
+    SpecError {
+      code,
+      field,
+      message,
+      at: Coord { line_num: error.line(), col: error.column() },
+    }
+  }
+
+  fn template(json: &str, language: &Language, error: TemplateError) -> Self {
+    // every spelling is a template of its own.
+    if let Some((key, element)) = error.template.strip_prefix("spellings.").and_then(|x| x.strip_suffix(']')?.split_once('[')) {
+      let spellings = if key == "rules" { &language.spellings.rules } else { &language.spellings.tokens };
+      return SpecError {
+        code: "L0005",
+        at: locate_template_line(json, key, spellings, element.parse().unwrap_or(0), error.col),
+        field: error.template,
+        message: error.message,
+      };
+    }
+
+    let (key, lines) = language.template_source(error.template.as_str());
+
+    // the template line numbers count lines of the joined template, find the string holding it.
+    let mut element = 0;
+    let mut first_line = 1;
+    while element + 1 < lines.len() && first_line + lines[element].matches('\n').count() < error.line {
+      first_line += lines[element].matches('\n').count() + 1;
+      element += 1;
+    }
+
+    SpecError {
+      code: "L0005",
+      // `file_stem` is the only template written as a single string.
+      field: if error.template == "file_stem" { error.template.clone() } else { format!("{}[{}]", error.template, element) },
+      message: error.message,
+      at: locate_template_line(json, key, lines, element, if first_line == error.line { error.col } else { 1 }),
+    }
+  }
+
+  pub fn to_diagnostic(&self) -> Diagnostic {
+    let label = if self.field.is_empty() { String::new() } else { format!("in `{}`", self.field) };
+
+    let mut diagnostic = Diagnostic::new(Severity::Error, self.code, self.message.clone())
+      .with_label(Label::primary(Span::at(self.at), label));
+
+    if self.code == "L0002" {
+      diagnostic = diagnostic.with_help("every field but `template` may be left out".to_string());
+    }
+
+    diagnostic
+  }
+}
+
+impl LanguageError {
+  /// Like `to_string`, but shows the offending part of an invalid spec.
+  pub fn render(&self, color: bool) -> String {
+    match self {
+      LanguageError::Invalid(path, source, error) => {
+        Renderer::new(path.display().to_string(), source, color).render(&error.to_diagnostic())
+      }
+      _ => format!("error: {}\n", self),
+    }
+  }
+}
+
+fn join_lines(lines: &[String]) -> String {
+  lines.iter().map(|x| format!("{}\n", x)).collect()
+}
+
+// best effort position of column `col` of `lines[element]`, the array stored under `key`.
+// spellings only get the identifier to print, check that this is enough for them.
+fn compile_spellings(key: &str, spellings: &[String]) -> Result<Vec<Template>, TemplateError> {
+  let mut result = vec![];
+
+  for (ndx, spelling) in spellings.iter().enumerate() {
+    let template = Template::parse(format!("spellings.{}[{}]", key, ndx).as_str(), spelling)?;
+    template.render(&serde_json::json!({ "name": "name" }), &BTreeMap::new())?;
+    result.push(template);
+  }
+
+  Ok(result)
+}
+
+fn locate_template_line(json: &str, key: &str, lines: &[String], element: usize, col: usize) -> Coord {
+  let mut position = json.find(format!("\"{}\"", key).as_str()).unwrap_or(0);
+
+  for (ndx, line) in lines.iter().enumerate().take(element + 1) {
+    let encoded = serde_json::to_string(line).unwrap();
+    let Some(offset) = json[position..].find(encoded.as_str()) else {
+      break;
+    };
+
+    position += offset;
+    if ndx == element {
+      let prefix: String = line.chars().take(col - 1).collect();
+      position += serde_json::to_string(&prefix).unwrap().len() - 1;
+    } else {
+      position += encoded.len();
+    }
+  }
+
+  let before = &json[..position];
+  let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+  Coord { line_num: before.matches('\n').count() + 1, col: before[line_start..].chars().count() + 1 }
+}
+
+/// Directories searched for language specs: `$PARSIFY_LANG_PATH`, then the user config directory.
+pub(crate) fn search_paths() -> Vec<PathBuf> {
+  let mut result = vec![];
+
+  if let Some(lang_path) = env::var_os(LANG_PATH_VAR) {
+    result.extend(env::split_paths(&lang_path).filter(|x| !x.as_os_str().is_empty()));
+  }
+
+  if let Some(config_dir) = config_dir() {
+    result.push(config_dir.join("parsify").join("langs"));
+  }
+
+  result
+}
+
+fn config_dir() -> Option<PathBuf> {
+  if cfg!(windows) {
+    return env::var_os("APPDATA").map(PathBuf::from);
+  }
+
+  match env::var_os("XDG_CONFIG_HOME") {
+    Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+    _ => env::var_os("HOME").map(|x| PathBuf::from(x).join(".config")),
+  }
+}
+
+// a width of zero would strip all indentation from the generated code.
+fn indent_width<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+  let width = usize::deserialize(deserializer)?;
+  if width == 0 {
+    return Err(D::Error::invalid_value(Unexpected::Unsigned(0), &"at least 1"));
+  }
+
+  Ok(width)
+}
+
+impl Display for LanguageError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LanguageError::NotFound(name, search_paths) => {
+        write!(f, "unknown language `{}`, it is not built in", name)?;
+        for dir in search_paths {
+          write!(f, "\n  and not found in {}", dir.display())?;
+        }
+
+        Ok(())
+      }
+      LanguageError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+      LanguageError::Invalid(path, _, error) => write!(f, "{}:{}:{}: {}", path.display(), error.at.line_num, error.at.col, error),
+    }
+  }
+}
+
+impl Display for SpecError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.field.is_empty() {
+      write!(f, "{}", self.message)
+    } else {
+      write!(f, "{}: {}", self.field, self.message)
+    }
+  }
+}
+
+impl Error for SpecError {}
+
+impl Error for LanguageError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      LanguageError::NotFound(_, _) => None,
+      LanguageError::Io(_, error) => Some(error),
+      LanguageError::Invalid(_, _, error) => Some(error.as_ref()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rejects_a_zero_indent_width() {
+    let json = "{\n  \"template\": [],\n  \"indent\": { \"width\": 0 }\n}";
+    let error = Language::from_json(json).err().unwrap();
+
+    assert_eq!(error.code, "L0003");
+    assert_eq!(error.field, "indent.width");
+    assert_eq!(error.message, "invalid value: integer `0`, expected at least 1");
+    assert_eq!(error.at.line_num, 3);
+  }
+
+  #[test]
+  fn names_unsupported_features_like_the_flag() {
+    let mut language = Language::from_json("{ \"template\": [] }").unwrap();
+    assert_eq!(language.enable(Feature::MatchDispatch).unwrap_err(), "the language does not support the `match-dispatch` feature");
+  }
+}
@@ -0,0 +1,56 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The grammar analysis and parser generation behind parsify, shared by the `parsify` crate and
+//! the `grammar!` macro in `parsify-macros`. Use it through `parsify`, which re-exports all of it.
+
+mod scanner;
+mod productions;
+mod language;
+mod generator;
+mod template;
+mod parser;
+mod error_handler;
+mod diagnostic;
+mod report;
+mod grammar;
+pub mod build;
+pub mod formatter;
+pub mod lsp;
+
+pub use diagnostic::{explain, ColorChoice, Diagnostic, Label, Renderer, Severity};
+pub use error_handler::{MessageFormat, Reporter};
+pub use generator::{GeneratedFile, DEFAULT_FILE_STEM};
+pub use grammar::{Grammar, GrammarError};
+pub use language::{AvailableLanguage, Feature, Indent, IndentStyle, Language, LanguageError, LanguageLocation, LineEnding, SpecError, Spellings};
+pub use productions::{Ambiguity, NonTerminal, Production};
+pub use report::ReportFormat;
+pub use scanner::{Coord, Span, Token};
+pub use template::TemplateError;
+
+/// Generates the source of a parser for `grammar` in the given language. Fails if the language's
+/// template uses a value the generator does not provide.
+pub fn generate(grammar: &Grammar, language: &Language) -> Result<String, TemplateError> {
+  generator::generate_parser(grammar.non_terminals(), language)
+}
+
+/// Generates every file of a parser for `grammar`, for languages that split parsers into several
+/// files. `file_stem` is the main file's name without its extension, used to refer to the others.
+pub fn generate_files(grammar: &Grammar, language: &Language, file_stem: &str) -> Result<Vec<GeneratedFile>, TemplateError> {
+  generator::generate_files(grammar.non_terminals(), language, file_stem)
+}
@@ -0,0 +1,417 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use serde_json::{json, Value};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::error_handler::{ambiguity_diagnostic, name_collision_diagnostic, parse_error_diagnostic, scan_error_diagnostic, undefined_symbol_diagnostic};
+use crate::generator::name_collisions;
+use crate::grammar::without_follow_on_errors;
+use crate::parser::Parser;
+use crate::productions::{self, NonTerminal};
+use crate::report::{generate_report, ReportFormat};
+use crate::scanner::{Coord, Scanner, Span, Token};
+
+// JSON-RPC error codes used by the language server protocol.
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const REQUEST_FAILED: i64 = -32803;
+
+// everything the server knows about one open grammar file.
+struct Analysis {
+  lines: Vec<String>,
+  tokens: Vec<Token>,
+  diagnostics: Vec<Diagnostic>,
+  non_terminals: Vec<NonTerminal>,
+}
+
+struct Server {
+  documents: HashMap<String, Analysis>,
+  shutdown_requested: bool,
+}
+
+fn analyze(text: &str) -> Analysis {
+  let mut diagnostics = vec![];
+  let mut non_terminals = vec![];
+
+  let mut scanner = Scanner::new(text.to_string());
+  let (tokens, scan_errors) = match scanner.scan() {
+    Ok(tokens) => (tokens, vec![]),
+    Err(errors) => (scanner.tokens().clone(), errors),
+  };
+  diagnostics.extend(scan_errors.iter().map(scan_error_diagnostic));
+
+  // the scanner resumes on the next line, so syntax errors elsewhere are still worth reporting.
+  let mut parser = Parser::new(tokens.clone());
+
+  match parser.parse() {
+    Ok(mut nts) => {
+      diagnostics.extend(parser.undefined_symbols().iter().map(undefined_symbol_diagnostic));
+
+      let ambiguities = productions::process(&mut nts);
+      diagnostics.extend(ambiguities.iter().map(ambiguity_diagnostic));
+      diagnostics.extend(name_collisions(&nts).into_iter().map(|(first, second)| name_collision_diagnostic(first, second)));
+      non_terminals = nts;
+    }
+    Err(errors) => diagnostics.extend(without_follow_on_errors(errors, &scan_errors, &tokens).iter().map(parse_error_diagnostic)),
+  }
+
+  Analysis {
+    lines: text.split('\n').map(|x| x.trim_end_matches('\r').to_string()).collect(),
+    tokens,
+    diagnostics,
+    non_terminals,
+  }
+}
+
+impl Analysis {
+  fn line(&self, line_num: usize) -> &str {
+    match line_num.checked_sub(1).and_then(|x| self.lines.get(x)) {
+      None => "",
+      Some(line) => line.as_str(),
+    }
+  }
+
+  // LSP positions count UTF-16 code units, coords count characters.
+  fn coord_to_position(&self, coord: Coord) -> Value {
+    let character: usize = self.line(coord.line_num).chars().take(coord.col.saturating_sub(1)).map(char::len_utf16).sum();
+    json!({ "line": coord.line_num.saturating_sub(1), "character": character })
+  }
+
+  fn position_to_coord(&self, position: &Value) -> Option<Coord> {
+    let line_num = position["line"].as_u64()? as usize + 1;
+    let character = position["character"].as_u64()? as usize;
+
+    let mut units = 0;
+    let mut col = 1;
+    for c in self.line(line_num).chars() {
+      if units >= character {
+        break;
+      }
+
+      units += c.len_utf16();
+      col += 1;
+    }
+
+    Some(Coord { line_num, col })
+  }
+
+  fn range(&self, span: Span) -> Value {
+    json!({ "start": self.coord_to_position(span.start), "end": self.coord_to_position(span.end) })
+  }
+
+  fn identifier_at(&self, position: &Value) -> Option<&Token> {
+    let coord = self.position_to_coord(position)?;
+
+    self.tokens.iter().find(|x| {
+      x.kind == "ID"
+        && x.span.start.line_num == coord.line_num
+        && x.span.start.col <= coord.col
+        && coord.col <= x.span.end.col
+    })
+  }
+
+  fn is_definition(&self, ndx: usize) -> bool {
+    self.tokens[ndx].kind == "ID" && self.tokens.get(ndx + 1).is_some_and(|x| x.kind == "EQUALS")
+  }
+
+  fn definitions(&self, name: &str) -> Vec<&Token> {
+    (0..self.tokens.len())
+      .filter(|x| self.is_definition(*x) && self.tokens[*x].value == name)
+      .map(|x| &self.tokens[x])
+      .collect()
+  }
+
+  fn references(&self, name: &str, include_declaration: bool) -> Vec<&Token> {
+    (0..self.tokens.len())
+      .filter(|x| self.tokens[*x].kind == "ID" && self.tokens[*x].value == name)
+      .filter(|x| include_declaration || !self.is_definition(*x))
+      .map(|x| &self.tokens[x])
+      .collect()
+  }
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+  let inner = match name.strip_prefix('<') {
+    Some(rest) => match rest.strip_suffix('>') {
+      Some(inner) => inner,
+      None => return false,
+    },
+    None => name,
+  };
+
+  let mut chars = inner.chars();
+  match chars.next() {
+    Some(c) if c == '_' || c.is_alphabetic() => {}
+    _ => return false,
+  }
+
+  chars.all(|c| c == '-' || c == '_' || c.is_alphabetic() || c.is_ascii_digit())
+}
+
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+  let mut content_length: Option<usize> = None;
+
+  loop {
+    let mut header = String::new();
+    if input.read_line(&mut header)? == 0 {
+      return Ok(None);
+    }
+
+    let header = header.trim_end();
+    if header.is_empty() {
+      break;
+    }
+
+    if let Some((name, value)) = header.split_once(':') {
+      if name.eq_ignore_ascii_case("Content-Length") {
+        content_length = value.trim().parse().ok();
+      }
+    }
+  }
+
+  let length = content_length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+  let mut body = vec![0; length];
+  input.read_exact(&mut body)?;
+
+  serde_json::from_slice(&body).map(Some).map_err(io::Error::from)
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+  let body = serde_json::to_string(message)?;
+  write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+  output.flush()
+}
+
+/// Serves grammar diagnostics, formatting and navigation over the Language Server Protocol on
+/// stdin and stdout until the client exits.
+pub fn run() -> io::Result<()> {
+  let stdin = io::stdin();
+  let mut input = stdin.lock();
+  let mut output = io::stdout();
+
+  let mut server = Server { documents: HashMap::new(), shutdown_requested: false };
+
+  while let Some(message) = read_message(&mut input)? {
+    let method = message["method"].as_str().unwrap_or("");
+
+    if method == "exit" {
+      break;
+    }
+
+    let params = &message["params"];
+    let Some(id) = message.get("id") else {
+      for notification in server.notification(method, params) {
+        write_message(&mut output, &notification)?;
+      }
+
+      continue;
+    };
+
+    let response = match server.request(method, params) {
+      Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+      Err((code, error)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": error } }),
+    };
+
+    write_message(&mut output, &response)?;
+  }
+
+  if !server.shutdown_requested {
+    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "client exited without shutdown"));
+  }
+
+  Ok(())
+}
+
+impl Server {
+  fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+      "initialize" => Ok(json!({
+        "capabilities": {
+          "textDocumentSync": 1,
+          "definitionProvider": true,
+          "referencesProvider": true,
+          "hoverProvider": true,
+          "renameProvider": true,
+        },
+        "serverInfo": { "name": "parsify", "version": env!("CARGO_PKG_VERSION") },
+      })),
+      "shutdown" => {
+        self.shutdown_requested = true;
+        Ok(Value::Null)
+      }
+      "textDocument/definition" => self.definition(params),
+      "textDocument/references" => self.references(params),
+      "textDocument/hover" => self.hover(params),
+      "textDocument/rename" => self.rename(params),
+      _ => Err((METHOD_NOT_FOUND, format!("unsupported method {}", method))),
+    }
+  }
+
+  // returns the notifications to send back to the client.
+  fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+
+    match method {
+      "textDocument/didOpen" => {
+        let text = params["textDocument"]["text"].as_str().unwrap_or("");
+        self.documents.insert(uri.clone(), analyze(text));
+        vec![self.publish_diagnostics(uri.as_str())]
+      }
+      "textDocument/didChange" => {
+        // full document sync, so the last change holds the whole text.
+        let Some(text) = params["contentChanges"].as_array().and_then(|x| x.last()).and_then(|x| x["text"].as_str()) else {
+          return vec![];
+        };
+
+        self.documents.insert(uri.clone(), analyze(text));
+        vec![self.publish_diagnostics(uri.as_str())]
+      }
+      "textDocument/didClose" => {
+        self.documents.remove(&uri);
+        vec![json!({
+          "jsonrpc": "2.0",
+          "method": "textDocument/publishDiagnostics",
+          "params": { "uri": uri, "diagnostics": [] },
+        })]
+      }
+      _ => vec![],
+    }
+  }
+
+  fn publish_diagnostics(&self, uri: &str) -> Value {
+    let analysis = &self.documents[uri];
+    let mut diagnostics = vec![];
+
+    for diagnostic in &analysis.diagnostics {
+      let Some(span) = diagnostic.primary_span() else {
+        continue;
+      };
+
+      let related: Vec<Value> = diagnostic.labels.iter().filter(|x| !x.is_primary).map(|x| json!({
+        "location": { "uri": uri, "range": analysis.range(x.span) },
+        "message": x.message,
+      })).collect();
+
+      let mut message = diagnostic.message.clone();
+      for label in diagnostic.labels.iter().filter(|x| x.is_primary && !x.message.is_empty()) {
+        message.push_str(format!("\n{}", label.message).as_str());
+      }
+      for note in &diagnostic.notes {
+        message.push_str(format!("\nnote: {}", note).as_str());
+      }
+      for help in &diagnostic.help {
+        message.push_str(format!("\nhelp: {}", help).as_str());
+      }
+
+      diagnostics.push(json!({
+        "range": analysis.range(span),
+        "severity": if diagnostic.severity == Severity::Error { 1 } else { 2 },
+        "code": diagnostic.code,
+        "source": "parsify",
+        "message": message,
+        "relatedInformation": related,
+      }));
+    }
+
+    json!({
+      "jsonrpc": "2.0",
+      "method": "textDocument/publishDiagnostics",
+      "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+  }
+
+  fn document<'a>(&'a self, params: &'a Value) -> Result<(&'a str, &'a Analysis), (i64, String)> {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+
+    match self.documents.get(uri) {
+      Some(analysis) => Ok((uri, analysis)),
+      None => Err((INVALID_PARAMS, format!("unknown document {}", uri))),
+    }
+  }
+
+  fn locations(uri: &str, analysis: &Analysis, tokens: Vec<&Token>) -> Value {
+    let locations: Vec<Value> = tokens.iter().map(|x| json!({ "uri": uri, "range": analysis.range(x.span) })).collect();
+    Value::Array(locations)
+  }
+
+  fn definition(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (uri, analysis) = self.document(params)?;
+
+    match analysis.identifier_at(&params["position"]) {
+      None => Ok(Value::Null),
+      Some(token) => Ok(Self::locations(uri, analysis, analysis.definitions(token.value.as_str()))),
+    }
+  }
+
+  fn references(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (uri, analysis) = self.document(params)?;
+    let include_declaration = params["context"]["includeDeclaration"].as_bool().unwrap_or(true);
+
+    match analysis.identifier_at(&params["position"]) {
+      None => Ok(Value::Null),
+      Some(token) => Ok(Self::locations(uri, analysis, analysis.references(token.value.as_str(), include_declaration))),
+    }
+  }
+
+  fn hover(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (_, analysis) = self.document(params)?;
+
+    let Some(token) = analysis.identifier_at(&params["position"]) else {
+      return Ok(Value::Null);
+    };
+
+    let contents = match analysis.non_terminals.iter().find(|x| x.name == token.value) {
+      Some(nt) => format!("```\n{}```", generate_report(std::slice::from_ref(nt), ReportFormat::Text)),
+      None if analysis.definitions(token.value.as_str()).is_empty() => format!("`{}` is a terminal", token.value),
+      // defined, but the grammar has errors so no sets were computed.
+      None => return Ok(Value::Null),
+    };
+
+    Ok(json!({
+      "contents": { "kind": "markdown", "value": contents },
+      "range": analysis.range(token.span),
+    }))
+  }
+
+  fn rename(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (uri, analysis) = self.document(params)?;
+    let new_name = params["newName"].as_str().unwrap_or("");
+
+    if !is_valid_identifier(new_name) {
+      return Err((INVALID_PARAMS, format!("`{}` is not a valid identifier", new_name)));
+    }
+
+    let Some(token) = analysis.identifier_at(&params["position"]) else {
+      return Ok(Value::Null);
+    };
+
+    // renaming onto a name in use would merge the two symbols.
+    if new_name != token.value && !analysis.references(new_name, true).is_empty() {
+      return Err((REQUEST_FAILED, format!("`{}` is already used in this grammar", new_name)));
+    }
+
+    let edits: Vec<Value> = analysis.references(token.value.as_str(), true).iter().map(|x| json!({
+      "range": analysis.range(x.span),
+      "newText": new_name,
+    })).collect();
+
+    Ok(json!({ "changes": { uri: edits } }))
+  }
+}
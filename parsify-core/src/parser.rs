@@ -1,4 +1,4 @@
-/**
+/*
  * Parsify, a simple recursive descent parser generator.
  * Copyright (C) 2024  Eduardo Ibarra
  *
@@ -19,36 +19,44 @@
 use std::collections::HashMap;
 use crate::productions::{NonTerminal, Production};
 use crate::parser::ParserError::UnexpectedToken;
-use crate::scanner::Token;
+use crate::scanner::{Span, Token};
 
 #[derive(Debug)]
-pub(crate) enum ParserError {
+pub enum ParserError {
   UnexpectedToken(Token, Vec<&'static str>)
 }
 
-pub(crate) struct Parser {
+pub struct Parser {
   scanner: Vec<Token>,
   current_ndx: usize,
   productions: HashMap<String, Vec<Production>>,
+  errors: Vec<ParserError>,
+  undefined: Vec<Token>,
 }
 
 impl Parser {
-  pub(crate) fn new(tokens: Vec<Token>) -> Self {
+  pub fn new(tokens: Vec<Token>) -> Self {
     Parser {
       scanner: tokens,
       current_ndx: 0,
       productions: HashMap::new(),
+      errors: vec![],
+      undefined: vec![],
     }
   }
 
   fn match_kind(&mut self, kind: &'static str) -> Result<Token, ParserError> {
-    return if self.current() == kind {
+    if self.current() == kind {
       let prev = self.scanner[self.current_ndx].clone();
       self.current_ndx += 1;
       Ok(prev)
     } else {
       Err(UnexpectedToken(self.current_token(), vec![kind]))
-    };
+    }
+  }
+
+  pub fn undefined_symbols(&self) -> &Vec<Token> {
+    &self.undefined
   }
 
   fn current(&self) -> &str {
@@ -58,16 +66,46 @@ impl Parser {
     self.scanner[self.current_ndx].clone()
   }
 
-  pub(crate) fn parse(&mut self) -> Result<Vec<NonTerminal>, ParserError> {
-    self.bnf_file()?;
-    self.match_kind("EOF")?;
+  // error recovery: skip ahead to the end of the current production.
+  fn synchronize(&mut self) {
+    while self.current() != "EOF" {
+      let was_end = self.current() == "END";
+      self.current_ndx += 1;
+
+      if was_end {
+        break;
+      }
+    }
+  }
+
+  fn recovering_production(&mut self) {
+    if let Err(error) = self.production() {
+      self.errors.push(error);
+      self.synchronize();
+    }
+  }
+
+  pub fn parse(&mut self) -> Result<Vec<NonTerminal>, Vec<ParserError>> {
+    if let Err(error) = self.bnf_file() {
+      self.errors.push(error);
+    }
+
+    if let Err(error) = self.match_kind("EOF") {
+      self.errors.push(error);
+    }
+
+    if !self.errors.is_empty() {
+      return Err(self.errors.drain(..).collect());
+    }
 
     let mut nt_order: Vec<String> = vec![];
+    let mut nt_spans: HashMap<String, Span> = HashMap::new();
 
     let mut prev = self.scanner.first().unwrap();
     for token in &self.scanner {
       if token.kind.eq("EQUALS") && !nt_order.contains(&prev.value) {
         nt_order.push(prev.value.clone());
+        nt_spans.insert(prev.value.clone(), prev.span);
       }
 
       prev = token;
@@ -83,6 +121,11 @@ impl Parser {
         let mut new_prod = Production::new();
         for token in &prod.list {
           if token.kind.eq("ID") && !self.productions.contains_key(&token.value) {
+            // bare identifiers without a rule are terminals, but <name> always refers to a rule.
+            if token.value.starts_with('<') {
+              self.undefined.push(token.clone());
+            }
+
             let mut new_token = token.clone();
             new_token.kind = "TERM".to_string();
             new_prod.push(new_token);
@@ -94,7 +137,7 @@ impl Parser {
         prods_sanitized.push(new_prod);
       }
 
-      let mut nt = NonTerminal::new(name.clone());
+      let mut nt = NonTerminal::new(name.clone(), nt_spans[name]);
       nt.productions = prods_sanitized;
       nt.is_start_term = nt_order.first().unwrap().eq(name);
       result.push(nt);
@@ -105,22 +148,24 @@ impl Parser {
 
   fn bnf_file(&mut self) -> Result<(), ParserError> {
     if ["ID"].contains(&self.current()) {
-      self.production()?;
+      self.recovering_production();
       self.production_list()?;
     } else {
-      return Err(UnexpectedToken(self.current_token(), vec!["ID"]));
+      self.errors.push(UnexpectedToken(self.current_token(), vec!["ID"]));
+      self.synchronize();
+      self.production_list()?;
     }
     Ok(())
   }
 
   fn production_list(&mut self) -> Result<(), ParserError> {
-    if ["ID"].contains(&self.current()) {
-      self.production()?;
-      self.production_list()?;
-    } else if ["EOF"].contains(&self.current()) {
-      // do nothing
-    } else {
-      return Err(UnexpectedToken(self.current_token(), vec!["EOF", "ID"]));
+    while !["EOF"].contains(&self.current()) {
+      if ["ID"].contains(&self.current()) {
+        self.recovering_production();
+      } else {
+        self.errors.push(UnexpectedToken(self.current_token(), vec!["EOF", "ID"]));
+        self.synchronize();
+      }
     }
     Ok(())
   }
@@ -187,12 +232,12 @@ impl Parser {
   }
 
   fn token(&mut self) -> Result<Token, ParserError> {
-    return if ["ID"].contains(&self.current()) {
+    if ["ID"].contains(&self.current()) {
       Ok(self.match_kind("ID")?)
     } else if ["TERM"].contains(&self.current()) {
       Ok(self.match_kind("TERM")?)
     } else {
       Err(UnexpectedToken(self.current_token(), vec!["ID", "TERM"]))
-    };
+    }
   }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-/**
+/*
  * Parsify, a simple recursive descent parser generator.
  * Copyright (C) 2024  Eduardo Ibarra
  *
@@ -18,33 +18,57 @@
 
 use std::collections::{HashMap, BTreeSet, HashSet};
 use std::hash::{Hash, Hasher};
-use crate::error_handler::print_ambiguity;
 use crate::productions::Nullable::{Maybe, No, Yes};
-use crate::scanner::Token;
+use crate::scanner::{Span, Token};
 
 #[derive(Eq, PartialEq, Debug, Clone)]
-pub(crate) enum Nullable {
+pub enum Nullable {
   No,
   Maybe,
   Yes
 }
 
+/// One alternative of a rule.
 #[derive(Debug, Clone)]
-pub(crate) struct Production {
-  pub(crate) list: Vec<Token>,
-  pub(crate) predict_set: BTreeSet<String>,
+pub struct Production {
+  /// The symbols in order, `ID` tokens for non-terminals and `TERM` tokens for terminals. Empty
+  /// for an empty alternative.
+  pub list: Vec<Token>,
+  /// Terminals that select this alternative, the empty string standing for the end of input.
+  pub predict_set: BTreeSet<String>,
   nullable: Nullable,
 }
 
+/// A rule of the grammar with its analysis results. Sets hold terminals as written, quotes
+/// included, and the empty string for the end of input.
 #[derive(Debug, Clone)]
-pub(crate) struct NonTerminal {
-  pub(crate) name: String,
-  pub(crate) is_start_term: bool,
-  pub(crate) is_nullable: bool,
-  pub(crate) first_set: BTreeSet<String>,
-  pub(crate) follow_set: BTreeSet<String>,
-  pub(crate) productions: Vec<Production>,
-  pub(crate) predict_set: BTreeSet<String>,
+pub struct NonTerminal {
+  pub name: String,
+  pub span: Span,
+  /// Whether this is the first rule, where parsing starts.
+  pub is_start_term: bool,
+  pub is_nullable: bool,
+  pub first_set: BTreeSet<String>,
+  pub follow_set: BTreeSet<String>,
+  /// The alternatives, every definition of the rule merged.
+  pub productions: Vec<Production>,
+  /// Union of the alternatives' predict sets.
+  pub predict_set: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ambiguity {
+  pub non_terminal: String,
+  pub span: Span,
+  pub tokens: Vec<String>,
+  // spans of the conflicting (non-empty) productions.
+  pub related: Vec<Span>,
+}
+
+impl Default for Production {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Production {
@@ -62,12 +86,20 @@ impl Production {
   pub(crate) fn push_to_front(&mut self, token: Token) {
     self.list.insert(0, token);
   }
+
+  /// From the first to the last symbol, `None` for an empty alternative.
+  pub fn span(&self) -> Option<Span> {
+    let first = self.list.first()?;
+    let last = self.list.last()?;
+    Some(Span { start: first.span.start, end: last.span.end })
+  }
 }
 
 impl NonTerminal {
-  pub(crate) fn new(name: String) -> Self {
+  pub(crate) fn new(name: String, span: Span) -> Self {
     NonTerminal {
       name,
+      span,
       is_nullable: false,
       is_start_term: false,
       first_set: BTreeSet::new(),
@@ -124,7 +156,7 @@ fn first_n_follow_set_dfs(
   }
 }
 
-pub(crate) fn process(non_terminals: &mut Vec<NonTerminal>) {
+pub fn process(non_terminals: &mut Vec<NonTerminal>) -> Vec<Ambiguity> {
   nullability(non_terminals);
 
   let mut nullable_info: HashMap<String, bool> = HashMap::new();
@@ -135,7 +167,7 @@ pub(crate) fn process(non_terminals: &mut Vec<NonTerminal>) {
   first_sets(non_terminals, &nullable_info);
   follow_sets(non_terminals, &nullable_info);
   predict_sets(non_terminals);
-  find_ambiguities(non_terminals);
+  let ambiguities = find_ambiguities(non_terminals);
 
   for nt in &mut *non_terminals {
     for prod in &mut nt.productions {
@@ -152,9 +184,11 @@ pub(crate) fn process(non_terminals: &mut Vec<NonTerminal>) {
       }
     }
   }
+
+  ambiguities
 }
 
-pub(crate) fn nullability(nts: &mut Vec<NonTerminal>) {
+pub fn nullability(nts: &mut Vec<NonTerminal>) {
   let mut nt_nullable_info: HashMap<String, Nullable> = HashMap::new();
 
   for nt in &mut *nts {
@@ -213,7 +247,7 @@ pub(crate) fn nullability(nts: &mut Vec<NonTerminal>) {
   }
 }
 
-pub(crate) fn first_sets(nts: &mut Vec<NonTerminal>, nullable_info: &HashMap<String, bool>) {
+pub fn first_sets(nts: &mut Vec<NonTerminal>, nullable_info: &HashMap<String, bool>) {
   let mut graph: HashMap<String, HashSet<Node>> = HashMap::new();
 
   for nt in &mut *nts {
@@ -244,7 +278,7 @@ pub(crate) fn first_sets(nts: &mut Vec<NonTerminal>, nullable_info: &HashMap<Str
   }
 }
 
-pub(crate) fn follow_sets(nts: &mut Vec<NonTerminal>, nullable_info: &HashMap<String, bool>) {
+pub fn follow_sets(nts: &mut Vec<NonTerminal>, nullable_info: &HashMap<String, bool>) {
   let mut graph: HashMap<String, HashSet<Node>> = HashMap::new();
   graph.insert("EOF".to_string(), HashSet::new()); // insert EOF into the graph.
 
@@ -336,7 +370,7 @@ pub(crate) fn follow_sets(nts: &mut Vec<NonTerminal>, nullable_info: &HashMap<St
   }
 }
 
-pub(crate) fn predict_sets(nts: &mut Vec<NonTerminal>) {
+pub fn predict_sets(nts: &mut Vec<NonTerminal>) {
   let nts_clone = nts.clone();
   for nt in &mut *nts {
     for prod in &mut nt.productions {
@@ -368,22 +402,38 @@ pub(crate) fn predict_sets(nts: &mut Vec<NonTerminal>) {
   }
 }
 
-pub(crate) fn find_ambiguities(nts: &Vec<NonTerminal>) {
+pub fn find_ambiguities(nts: &Vec<NonTerminal>) -> Vec<Ambiguity> {
+  let mut result = vec![];
+
   for nt in nts {
     let mut seen_prediction_tokens = BTreeSet::new();
 
-    for prod in &nt.productions {
-      let intersection = seen_prediction_tokens.intersection(&prod.predict_set);
-      if intersection.clone().count() > 0 {
-        print_ambiguity(&nt.name, intersection);
-      }
+    for (ndx, prod) in nt.productions.iter().enumerate() {
+      let intersection: BTreeSet<String> = seen_prediction_tokens.intersection(&prod.predict_set).cloned().collect();
+      if !intersection.is_empty() {
+        let mut related = vec![];
 
-      let mut vector = vec![];
-      for str in &prod.predict_set {
-        vector.push(str.clone());
+        for prev in &nt.productions[..ndx] {
+          if prev.predict_set.is_disjoint(&intersection) {
+            continue;
+          }
+
+          related.extend(prev.span());
+        }
+
+        related.extend(prod.span());
+
+        result.push(Ambiguity {
+          non_terminal: nt.name.clone(),
+          span: nt.span,
+          tokens: intersection.into_iter().collect(),
+          related,
+        });
       }
 
-      seen_prediction_tokens.extend(vector);
+      seen_prediction_tokens.extend(prod.predict_set.iter().cloned());
     }
   }
+
+  result
 }
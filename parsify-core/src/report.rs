@@ -0,0 +1,125 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeSet;
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::productions::NonTerminal;
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReportFormat {
+  Text,
+  Json,
+}
+
+#[derive(Serialize)]
+struct ProductionReport {
+  tokens: Vec<String>,
+  predict_set: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct NonTerminalReport {
+  name: String,
+  is_start_term: bool,
+  is_nullable: bool,
+  first_set: Vec<String>,
+  follow_set: Vec<String>,
+  predict_set: Vec<String>,
+  productions: Vec<ProductionReport>,
+}
+
+fn set_to_vec(set: &BTreeSet<String>) -> Vec<String> {
+  set.iter().cloned().collect()
+}
+
+fn build_report(non_terminals: &[NonTerminal]) -> Vec<NonTerminalReport> {
+  let mut result = vec![];
+
+  for nt in non_terminals {
+    let mut productions = vec![];
+
+    for prod in &nt.productions {
+      productions.push(ProductionReport {
+        tokens: prod.list.iter().map(|x| x.value.clone()).collect(),
+        predict_set: set_to_vec(&prod.predict_set),
+      });
+    }
+
+    result.push(NonTerminalReport {
+      name: nt.name.clone(),
+      is_start_term: nt.is_start_term,
+      is_nullable: nt.is_nullable,
+      first_set: set_to_vec(&nt.first_set),
+      follow_set: set_to_vec(&nt.follow_set),
+      predict_set: set_to_vec(&nt.predict_set),
+      productions,
+    });
+  }
+
+  result
+}
+
+fn format_set(set: &[String]) -> String {
+  if set.is_empty() {
+    return "(none)".to_string();
+  }
+
+  set.join(", ")
+}
+
+fn text_report(reports: &[NonTerminalReport]) -> String {
+  let mut result = String::new();
+
+  for nt in reports {
+    result.push_str(nt.name.as_str());
+    if nt.is_start_term {
+      result.push_str(" (start)");
+    }
+    result.push('\n');
+
+    result.push_str(format!("  nullable: {}\n", if nt.is_nullable { "yes" } else { "no" }).as_str());
+    result.push_str(format!("  first:    {}\n", format_set(&nt.first_set)).as_str());
+    result.push_str(format!("  follow:   {}\n", format_set(&nt.follow_set)).as_str());
+    result.push_str(format!("  predict:  {}\n", format_set(&nt.predict_set)).as_str());
+    result.push_str("  productions:\n");
+
+    for prod in &nt.productions {
+      let rhs = if prod.tokens.is_empty() { "(empty)".to_string() } else { prod.tokens.join(" ") };
+      result.push_str(format!("    {} ::= {}\n", nt.name, rhs).as_str());
+      result.push_str(format!("      predict: {}\n", format_set(&prod.predict_set)).as_str());
+    }
+
+    result.push('\n');
+  }
+
+  result
+}
+
+pub fn generate_report(non_terminals: &[NonTerminal], format: ReportFormat) -> String {
+  let reports = build_report(non_terminals);
+
+  match format {
+    ReportFormat::Text => text_report(&reports),
+    ReportFormat::Json => {
+      let mut result = serde_json::to_string_pretty(&reports).unwrap();
+      result.push('\n');
+      result
+    }
+  }
+}
@@ -1,4 +1,4 @@
-/**
+/*
  * Parsify, a simple recursive descent parser generator.
  * Copyright (C) 2024  Eduardo Ibarra
  *
@@ -20,21 +20,30 @@ use crate::scanner::ScanError::{NoMoreChars, UnexpectedChar};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Coord {
-  pub(crate) line_num: usize,
-  pub(crate) col: usize,
+  pub line_num: usize,
+  pub col: usize,
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Span {
-  pub(crate) start: Coord,
-  pub(crate) end: Coord,
+  pub start: Coord,
+  pub end: Coord,
 }
 
+impl Span {
+  // a single character wide span.
+  pub fn at(coord: Coord) -> Self {
+    Span { start: coord, end: Coord { line_num: coord.line_num, col: coord.col + 1 } }
+  }
+}
+
+/// A lexeme of the grammar source. `kind` is `ID`, `TERM`, `EQUALS`, `END`, `|` or `EOF`, and
+/// `value` the text as written.
 #[derive(Debug, Clone)]
 pub struct Token {
-  pub(crate) kind: String,
-  pub(crate) value: String,
-  pub(crate) span: Span,
+  pub kind: String,
+  pub value: String,
+  pub span: Span,
 }
 
 #[derive(Debug)]
@@ -44,73 +53,50 @@ pub enum ScanError {
   NoMoreChars(Coord),
 }
 
-pub(crate) struct Scanner {
-  file: String,
+impl ScanError {
+  pub fn coord(&self) -> Coord {
+    match self {
+      UnexpectedChar(_, _, at) | NoMoreChars(at) => *at,
+    }
+  }
+}
+
+pub struct Scanner {
+  file: Vec<char>,
   next_char: usize,
   tokens: Vec<Token>,
   seen_newlines: usize,
   last_seen_newline_ndx: i64,
+  keep_comments: bool,
 }
 
 impl Scanner {
-  pub(crate) fn new(file: String) -> Self {
+  pub fn new(file: String) -> Self {
     Scanner {
-      file,
+      file: file.chars().collect(),
       next_char: 0,
       tokens: vec![],
       seen_newlines: 0,
       last_seen_newline_ndx: -1,
+      keep_comments: false,
     }
   }
 
-  pub(crate) fn scan(&mut self) -> Result<Vec<Token>, ScanError> {
-    while self.has_next() {
-      let start_of_token = self.next_char;
-      let kind: String;
+  // emit COMMENT tokens instead of skipping comments.
+  pub fn with_comments(file: String) -> Self {
+    let mut scanner = Scanner::new(file);
+    scanner.keep_comments = true;
+    scanner
+  }
 
-      let current = self.current()?;
-      if current.is_whitespace() {
-        self.whitespace()?;
-        continue; // do not make whitespace tokens.
-      } else if current == '/' {
-        self.comment()?;
-        continue; // do not make comment tokens.
-      } else if current == '<' || current == '_' || current.is_alphabetic() {
-        self.identifier()?;
-        kind = "ID".to_string();
-      } else if current == '"' || current == '\'' {
-        self.literal()?;
-        kind = "TERM".to_string();
-      } else if current == ':' {
-        // either ':' or '::=' is an equals token.
-        self.match_char(':')?;
+  pub fn scan(&mut self) -> Result<Vec<Token>, Vec<ScanError>> {
+    let mut errors = vec![];
 
-        if self.current()? == ':' {
-          self.match_char(':')?;
-          self.match_char('=')?;
-        }
-
-        kind = "EQUALS".to_string();
-      } else if current == ';' {
-        self.match_char(';')?;
-        kind = "END".to_string();
-      } else if current == '.' {
-        self.match_char('.')?;
-        kind = "END".to_string();
-      } else if current == '|' {
-        self.match_char('|')?;
-        kind = "|".to_string();
-      } else {
-        return Err(UnexpectedChar('_', current, self.index_to_coord(self.next_char)));
+    while self.has_next() {
+      if let Err(error) = self.scan_token() {
+        errors.push(error);
+        self.skip_line();
       }
-
-      let value = self.file[start_of_token..self.next_char].to_string();
-
-      self.tokens.push(Token {
-        kind,
-        value,
-        span: Span { start: self.index_to_coord(start_of_token), end: self.index_to_coord(self.next_char) },
-      });
     }
 
     self.tokens.push(Token {
@@ -119,9 +105,91 @@ impl Scanner {
       span: Span { start: self.index_to_coord(self.next_char), end: self.index_to_coord(self.next_char) },
     });
 
+    if !errors.is_empty() {
+      return Err(errors);
+    }
+
     Ok(self.tokens.clone())
   }
 
+  // tokens scanned so far, including the ones recovered around scan errors.
+  pub fn tokens(&self) -> &Vec<Token> {
+    &self.tokens
+  }
+
+  fn scan_token(&mut self) -> Result<(), ScanError> {
+    let start_of_token = self.next_char;
+    let kind: String;
+
+    let current = self.current()?;
+    if current.is_whitespace() {
+      self.whitespace()?;
+      return Ok(()); // do not make whitespace tokens.
+    } else if current == '/' {
+      self.comment()?;
+
+      if !self.keep_comments {
+        return Ok(()); // do not make comment tokens.
+      }
+
+      kind = "COMMENT".to_string();
+    } else if current == '<' || current == '_' || current.is_alphabetic() {
+      self.identifier()?;
+      kind = "ID".to_string();
+    } else if current == '"' || current == '\'' {
+      self.literal()?;
+      kind = "TERM".to_string();
+    } else if current == ':' {
+      // either ':' or '::=' is an equals token.
+      self.match_char(':')?;
+
+      if self.current()? == ':' {
+        self.match_char(':')?;
+        self.match_char('=')?;
+      }
+
+      kind = "EQUALS".to_string();
+    } else if current == ';' {
+      self.match_char(';')?;
+      kind = "END".to_string();
+    } else if current == '.' {
+      self.match_char('.')?;
+      kind = "END".to_string();
+    } else if current == '|' {
+      self.match_char('|')?;
+      kind = "|".to_string();
+    } else {
+      return Err(UnexpectedChar('_', current, self.index_to_coord(self.next_char)));
+    }
+
+    let value: String = self.file[start_of_token..self.next_char].iter().collect();
+
+    self.tokens.push(Token {
+      kind,
+      value,
+      span: Span { start: self.index_to_coord(start_of_token), end: self.index_to_coord(self.next_char) },
+    });
+
+    Ok(())
+  }
+
+  // error recovery: drop the rest of the current line and resume scanning on the next one.
+  fn skip_line(&mut self) {
+    while self.has_next() {
+      let is_newline = self.file[self.next_char] == '\n';
+      if is_newline {
+        self.seen_newlines += 1;
+        self.last_seen_newline_ndx = self.next_char as i64;
+      }
+
+      self.next_char += 1;
+
+      if is_newline {
+        break;
+      }
+    }
+  }
+
   fn has_next(&self) -> bool {
     self.next_char < self.file.len()
   }
@@ -131,10 +199,7 @@ impl Scanner {
       return Err(NoMoreChars(self.index_to_coord(self.next_char)));
     }
 
-    match self.file.chars().nth(self.next_char) {
-      None => Err(NoMoreChars(self.index_to_coord(self.next_char))),
-      Some(character) => Ok(character)
-    }
+    Ok(self.file[self.next_char])
   }
 
   fn match_char(&mut self, expected: char) -> Result<(), ScanError> {
@@ -162,18 +227,11 @@ impl Scanner {
     self.match_char('/')?;
     self.match_char('/')?;
 
+    // the newline is left for whitespace() so comments on the last line need not end with one.
     while self.has_next() && self.current()? != '\n' {
       self.match_char(self.current()?)?;
     }
 
-    if self.has_next() && self.current()? == '\n' {
-      self.seen_newlines += 1;
-      self.last_seen_newline_ndx = self.next_char as i64;
-      self.match_char('\n')?;
-    } else {
-      return Err(UnexpectedChar('\n', self.current()?, self.index_to_coord(self.next_char))); // unicorn character
-    }
-
     Ok(())
   }
 
@@ -204,7 +262,7 @@ impl Scanner {
       self.match_char(current)?;
     }
 
-    while self.current()? == '-' || self.current()? == '_' || self.current()?.is_alphabetic() || self.current()?.is_digit(10) {
+    while self.current()? == '-' || self.current()? == '_' || self.current()?.is_alphabetic() || self.current()?.is_ascii_digit() {
       self.match_char(self.current()?)?;
     }
 
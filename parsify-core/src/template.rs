@@ -0,0 +1,604 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A small logic-less template language for language specs.
+//!
+//! * `{{name}}` prints a value, `{{rule.name}}` looks up nested fields. Names are searched from
+//!   the innermost loop outwards, `this` is the current loop item and `@index`, `@first` and
+//!   `@last` describe its position.
+//! * `{{#each list}} ... {{/each}}` repeats its body for every item of a list.
+//! * `{{#if name}} ... {{else}} ... {{/if}}` and `{{#unless name}} ... {{/unless}}` test a
+//!   value; `false`, `null`, `0`, `""` and `[]` are false.
+//! * `{{> name}}` includes a partial template, `{{! text}}` is a comment.
+//! * Extra braces around a tag are printed, `{{{name}}}` prints the value of `name` in braces.
+//! * Values can be passed through filters, `{{name | identifier | upper}}`. `upper`, `lower` and
+//!   `capitalize` change case, `pascal` turns `snake_case` into `PascalCase` and `identifier`
+//!   replaces characters that cannot appear in an identifier with `_`.
+//!
+//! A line holding nothing but a block tag, comment or partial is left out of the output, so
+//! blocks can sit on lines of their own. Every line of a multi-line value or partial is indented
+//! like the line it is inserted on.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use serde_json::Value;
+
+// guards against partials that include themselves.
+const MAX_PARTIAL_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+  // name of the template the error is in.
+  pub template: String,
+  pub message: String,
+  pub line: usize,
+  pub col: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum BlockKind {
+  Each,
+  If,
+  Unless,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Filter {
+  Upper,
+  Lower,
+  Capitalize,
+  Pascal,
+  Identifier,
+}
+
+#[derive(Debug, Clone)]
+struct Tag {
+  content: String,
+  line: usize,
+  col: usize,
+}
+
+#[derive(Debug)]
+enum Piece {
+  Text(String),
+  Tag(Tag),
+}
+
+#[derive(Debug)]
+enum Node {
+  Text(String),
+  Value(Tag, Vec<Filter>),
+  Block { kind: BlockKind, tag: Tag, body: Vec<Node>, otherwise: Vec<Node> },
+  // indent is set for partials standing on a line of their own.
+  Partial { tag: Tag, indent: Option<String> },
+}
+
+// a block whose closing tag has not been seen yet.
+struct OpenBlock {
+  kind: BlockKind,
+  tag: Tag,
+  // nodes collected before the block was opened.
+  outer: Vec<Node>,
+  // nodes of the body once `else` was seen.
+  body: Option<Vec<Node>>,
+}
+
+#[derive(Debug)]
+pub struct Template {
+  name: String,
+  nodes: Vec<Node>,
+}
+
+struct Scope<'a> {
+  value: &'a Value,
+  // index and length of the list being looped over.
+  position: Option<(usize, usize)>,
+}
+
+impl BlockKind {
+  fn from_str(name: &str) -> Option<BlockKind> {
+    match name {
+      "each" => Some(BlockKind::Each),
+      "if" => Some(BlockKind::If),
+      "unless" => Some(BlockKind::Unless),
+      _ => None,
+    }
+  }
+
+  fn as_str(&self) -> &'static str {
+    match self {
+      BlockKind::Each => "each",
+      BlockKind::If => "if",
+      BlockKind::Unless => "unless",
+    }
+  }
+}
+
+impl Filter {
+  fn from_str(name: &str) -> Option<Filter> {
+    match name {
+      "upper" => Some(Filter::Upper),
+      "lower" => Some(Filter::Lower),
+      "capitalize" => Some(Filter::Capitalize),
+      "pascal" => Some(Filter::Pascal),
+      "identifier" => Some(Filter::Identifier),
+      _ => None,
+    }
+  }
+
+  fn apply(&self, value: String) -> String {
+    match self {
+      Filter::Upper => value.to_uppercase(),
+      Filter::Lower => value.to_lowercase(),
+      Filter::Capitalize => {
+        let mut chars = value.chars();
+        match chars.next() {
+          Some(first) => first.to_uppercase().chain(chars).collect(),
+          None => value,
+        }
+      }
+      Filter::Pascal => pascal_case(value.as_str()),
+      Filter::Identifier => {
+        let mut result: String = value.chars().map(|x| if x.is_alphanumeric() || x == '_' { x } else { '_' }).collect();
+        if !result.starts_with(|x: char| x.is_alphabetic() || x == '_') {
+          result.insert(0, '_');
+        }
+
+        result
+      }
+    }
+  }
+}
+
+/// `snake_case` to `PascalCase`, as the `pascal` filter prints it.
+pub(crate) fn pascal_case(value: &str) -> String {
+  value.split('_').map(|x| Filter::Capitalize.apply(x.to_string())).collect()
+}
+
+impl Tag {
+  fn is_standalone_kind(&self) -> bool {
+    self.content.starts_with(['#', '/', '!', '>']) || self.content == "else"
+  }
+
+  fn argument(&self) -> &str {
+    self.content[1..].trim()
+  }
+}
+
+fn is_valid_path(path: &str) -> bool {
+  !path.is_empty() && path.split('.').all(|x| !x.is_empty() && x.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '@'))
+}
+
+fn lex_line(template: &str, line: &str, line_num: usize) -> Result<Vec<Piece>, TemplateError> {
+  let mut pieces = vec![];
+  let mut rest = line;
+  let mut col = 1;
+
+  while let Some(mut start) = rest.find("{{") {
+    // in `{{{name}}}` the outer braces are text, which suits languages that use braces for blocks.
+    while rest[start + 2..].starts_with('{') {
+      start += 1;
+    }
+
+    if start != 0 {
+      pieces.push(Piece::Text(rest[..start].to_string()));
+    }
+
+    col += rest[..start].chars().count();
+
+    let Some(len) = rest[start + 2..].find("}}") else {
+      return Err(TemplateError::new(template, "`{{` is never closed by `}}`", line_num, col));
+    };
+
+    let content = rest[start + 2..start + 2 + len].trim().to_string();
+    pieces.push(Piece::Tag(Tag { content, line: line_num, col }));
+
+    col += rest[start..start + len + 4].chars().count();
+    rest = &rest[start + len + 4..];
+  }
+
+  if !rest.is_empty() {
+    pieces.push(Piece::Text(rest.to_string()));
+  }
+
+  Ok(pieces)
+}
+
+// drops the whitespace and newline around block tags, comments and partials that stand on a line of
+// their own, remembering the indentation of partials.
+fn strip_standalone(pieces: Vec<Piece>) -> Vec<(Piece, Option<String>)> {
+  let tags: Vec<&Tag> = pieces.iter().filter_map(|x| if let Piece::Tag(tag) = x { Some(tag) } else { None }).collect();
+  let only_whitespace = pieces.iter().all(|x| match x {
+    Piece::Text(text) => text.trim().is_empty(),
+    Piece::Tag(_) => true,
+  });
+
+  if tags.len() != 1 || !tags[0].is_standalone_kind() || !only_whitespace {
+    return pieces.into_iter().map(|x| (x, None)).collect();
+  }
+
+  let indent = match pieces.first() {
+    Some(Piece::Text(text)) => text.trim_end_matches(['\n', '\r']).to_string(),
+    _ => String::new(),
+  };
+
+  pieces.into_iter().filter(|x| matches!(x, Piece::Tag(_))).map(|x| (x, Some(indent.clone()))).collect()
+}
+
+impl Template {
+  pub fn parse(name: &str, source: &str) -> Result<Template, TemplateError> {
+    let mut pieces = vec![];
+    for (ndx, line) in source.split_inclusive('\n').enumerate() {
+      pieces.extend(strip_standalone(lex_line(name, line, ndx + 1)?));
+    }
+
+    let mut stack: Vec<OpenBlock> = vec![];
+    let mut nodes: Vec<Node> = vec![];
+
+    for (piece, indent) in pieces {
+      let tag = match piece {
+        Piece::Text(text) => {
+          nodes.push(Node::Text(text));
+          continue;
+        }
+        Piece::Tag(tag) => tag,
+      };
+
+      let error = |message: String| TemplateError::new(name, message.as_str(), tag.line, tag.col);
+
+      if tag.content.starts_with('!') {
+        continue;
+      } else if let Some(open) = tag.content.strip_prefix('#') {
+        let (kind_name, path) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+        let Some(kind) = BlockKind::from_str(kind_name) else {
+          return Err(error(format!("unknown block `#{}`, expected `#each`, `#if` or `#unless`", kind_name)));
+        };
+
+        if !is_valid_path(path.trim()) {
+          return Err(error(format!("`#{}` needs a name to test or loop over", kind_name)));
+        }
+
+        let tag = Tag { content: path.trim().to_string(), ..tag };
+        stack.push(OpenBlock { kind, tag, outer: std::mem::take(&mut nodes), body: None });
+      } else if tag.content == "else" {
+        match stack.last_mut() {
+          Some(OpenBlock { kind: BlockKind::Each, .. }) | None => return Err(error("`else` outside of an `#if` or `#unless` block".to_string())),
+          Some(OpenBlock { body: Some(_), .. }) => return Err(error("a block can only have one `else`".to_string())),
+          Some(open) => open.body = Some(std::mem::take(&mut nodes)),
+        }
+      } else if tag.content.starts_with('/') {
+        let Some(OpenBlock { kind, tag: open, outer, body }) = stack.pop() else {
+          return Err(error(format!("`{{{{{}}}}}` closes a block that was never opened", tag.content)));
+        };
+
+        if tag.argument() != kind.as_str() {
+          return Err(error(format!("expected `{{{{/{}}}}}` to close the block opened at {}:{}", kind.as_str(), open.line, open.col)));
+        }
+
+        let (body, otherwise) = match body {
+          Some(body) => (body, std::mem::replace(&mut nodes, outer)),
+          None => (std::mem::replace(&mut nodes, outer), vec![]),
+        };
+
+        nodes.push(Node::Block { kind, tag: open, body, otherwise });
+      } else if tag.content.starts_with('>') {
+        if tag.argument().is_empty() {
+          return Err(error("`>` needs the name of a partial".to_string()));
+        }
+
+        nodes.push(Node::Partial { tag, indent });
+      } else {
+        let mut parts = tag.content.split('|').map(|x| x.trim());
+        let path = parts.next().unwrap();
+        if !is_valid_path(path) {
+          return Err(error(format!("`{}` is not a valid name", path)));
+        }
+
+        let mut filters = vec![];
+        for name in parts {
+          match Filter::from_str(name) {
+            Some(filter) => filters.push(filter),
+            None => return Err(error(format!("unknown filter `{}`, expected `upper`, `lower`, `capitalize`, `pascal` or `identifier`", name))),
+          }
+        }
+
+        nodes.push(Node::Value(Tag { content: path.to_string(), ..tag }, filters));
+      }
+    }
+
+    if let Some(open) = stack.pop() {
+      return Err(TemplateError::new(name, format!("`#{}` block is never closed", open.kind.as_str()).as_str(), open.tag.line, open.tag.col));
+    }
+
+    Ok(Template { name: name.to_string(), nodes })
+  }
+
+  /// Fails on the first partial this template includes that `exists` does not know.
+  pub fn check_partials(&self, exists: impl Fn(&str) -> bool) -> Result<(), TemplateError> {
+    let mut tags = vec![];
+    collect_partials(&self.nodes, &mut tags);
+
+    match tags.into_iter().find(|x| !exists(x.argument())) {
+      Some(tag) => Err(self.error(format!("unknown partial `{}`", tag.argument()), tag)),
+      None => Ok(()),
+    }
+  }
+
+  pub fn render(&self, data: &Value, partials: &BTreeMap<String, Template>) -> Result<String, TemplateError> {
+    let mut output = String::new();
+    let mut scopes = vec![Scope { value: data, position: None }];
+    self.render_nodes(&self.nodes, &mut scopes, partials, 0, &mut output)?;
+    Ok(output)
+  }
+
+  fn render_nodes<'a>(&self, nodes: &[Node], scopes: &mut Vec<Scope<'a>>, partials: &BTreeMap<String, Template>, depth: usize, output: &mut String) -> Result<(), TemplateError> {
+    for node in nodes {
+      match node {
+        Node::Text(text) => output.push_str(text.as_str()),
+        Node::Value(tag, filters) => {
+          let value = self.lookup(scopes, tag)?;
+          let text = match value.as_ref() {
+            Value::Null => String::new(),
+            Value::Bool(value) => value.to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::String(value) => value.clone(),
+            Value::Array(_) | Value::Object(_) => {
+              return Err(self.error(format!("`{}` is a list or object and cannot be printed, loop over it with `#each`", tag.content), tag));
+            }
+          };
+
+          let text = filters.iter().fold(text, |text, filter| filter.apply(text));
+          insert_indented(output, text.as_str());
+        }
+        Node::Block { kind: BlockKind::Each, tag, body, .. } => {
+          // the loop borrows from the data, so resolve it against the scope it lives in.
+          let items: &'a Value = self.lookup_ref(scopes, tag)?;
+          let Value::Array(items) = items else {
+            return Err(self.error(format!("`#each` needs a list, but `{}` is not one", tag.content), tag));
+          };
+
+          for (ndx, item) in items.iter().enumerate() {
+            scopes.push(Scope { value: item, position: Some((ndx, items.len())) });
+            let result = self.render_nodes(body, scopes, partials, depth, output);
+            scopes.pop();
+            result?;
+          }
+        }
+        Node::Block { kind, tag, body, otherwise } => {
+          let mut condition = is_truthy(self.lookup(scopes, tag)?.as_ref());
+          if *kind == BlockKind::Unless {
+            condition = !condition;
+          }
+
+          self.render_nodes(if condition { body } else { otherwise }, scopes, partials, depth, output)?;
+        }
+        Node::Partial { tag, indent } => {
+          let name = tag.argument();
+          let Some(partial) = partials.get(name) else {
+            return Err(self.error(format!("unknown partial `{}`", name), tag));
+          };
+
+          if depth >= MAX_PARTIAL_DEPTH {
+            return Err(self.error(format!("partial `{}` includes itself too often", name), tag));
+          }
+
+          let mut text = String::new();
+          partial.render_nodes(&partial.nodes, scopes, partials, depth + 1, &mut text)?;
+
+          match indent {
+            Some(indent) => {
+              for line in text.split_inclusive('\n') {
+                if !line.trim().is_empty() {
+                  output.push_str(indent.as_str());
+                }
+
+                output.push_str(line);
+              }
+
+              if !text.is_empty() && !text.ends_with('\n') {
+                output.push('\n');
+              }
+            }
+            None => insert_indented(output, text.strip_suffix('\n').unwrap_or(text.as_str())),
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn lookup<'a>(&self, scopes: &[Scope<'a>], tag: &Tag) -> Result<Cow<'a, Value>, TemplateError> {
+    let path = tag.content.as_str();
+    let position = scopes.iter().rev().find_map(|x| x.position);
+
+    let value = match (path, position) {
+      ("@index", Some((ndx, _))) => Value::from(ndx),
+      ("@first", Some((ndx, _))) => Value::from(ndx == 0),
+      ("@last", Some((ndx, len))) => Value::from(ndx + 1 == len),
+      _ if path.starts_with('@') => return Err(self.error(format!("`{}` is only defined inside `#each`", path), tag)),
+      _ => return self.lookup_ref(scopes, tag).map(Cow::Borrowed),
+    };
+
+    Ok(Cow::Owned(value))
+  }
+
+  fn lookup_ref<'a>(&self, scopes: &[Scope<'a>], tag: &Tag) -> Result<&'a Value, TemplateError> {
+    let mut segments = tag.content.split('.');
+    let first = segments.next().unwrap();
+
+    let mut value = if first == "this" {
+      scopes.last().unwrap().value
+    } else {
+      let found = scopes.iter().rev().find_map(|x| x.value.get(first));
+      found.ok_or_else(|| self.error(format!("unknown name `{}`", first), tag))?
+    };
+
+    for segment in segments {
+      value = value.get(segment).ok_or_else(|| self.error(format!("`{}` has no field `{}`", tag.content, segment), tag))?;
+    }
+
+    Ok(value)
+  }
+
+  fn error(&self, message: String, tag: &Tag) -> TemplateError {
+    TemplateError::new(self.name.as_str(), message.as_str(), tag.line, tag.col)
+  }
+}
+
+fn collect_partials<'a>(nodes: &'a [Node], result: &mut Vec<&'a Tag>) {
+  for node in nodes {
+    match node {
+      Node::Partial { tag, .. } => result.push(tag),
+      Node::Block { body, otherwise, .. } => {
+        collect_partials(body, result);
+        collect_partials(otherwise, result);
+      }
+      _ => {}
+    }
+  }
+}
+
+fn is_truthy(value: &Value) -> bool {
+  match value {
+    Value::Null => false,
+    Value::Bool(value) => *value,
+    Value::Number(value) => value.as_f64() != Some(0.0),
+    Value::String(value) => !value.is_empty(),
+    Value::Array(value) => !value.is_empty(),
+    Value::Object(_) => true,
+  }
+}
+
+// continues every line of a multi-line value at the indentation of the line it starts on.
+fn insert_indented(output: &mut String, text: &str) {
+  let line_start = output.rfind('\n').map_or(0, |x| x + 1);
+  let indent: String = output[line_start..].chars().take_while(|x| *x == ' ' || *x == '\t').collect();
+
+  for (ndx, line) in text.split('\n').enumerate() {
+    if ndx != 0 {
+      output.push('\n');
+
+      if !line.is_empty() {
+        output.push_str(indent.as_str());
+      }
+    }
+
+    output.push_str(line);
+  }
+}
+
+impl TemplateError {
+  fn new(template: &str, message: &str, line: usize, col: usize) -> Self {
+    TemplateError { template: template.to_string(), message: message.to_string(), line, col }
+  }
+}
+
+impl Display for TemplateError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} line {}, column {}: {}", self.template, self.line, self.col, self.message)
+  }
+}
+
+impl Error for TemplateError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn render(source: &str, data: Value) -> String {
+    render_with(source, data, &[])
+  }
+
+  fn render_with(source: &str, data: Value, partials: &[(&str, &str)]) -> String {
+    let partials = partials.iter().map(|(name, source)| (name.to_string(), Template::parse(name, source).unwrap())).collect();
+    Template::parse("test", source).unwrap().render(&data, &partials).unwrap()
+  }
+
+  fn parse_error(source: &str) -> String {
+    Template::parse("test", source).unwrap_err().message
+  }
+
+  #[test]
+  fn prints_values_and_fields() {
+    assert_eq!(render("{{a}} {{b.c}} {{n}} {{t}}", json!({ "a": "x", "b": { "c": "y" }, "n": 3, "t": true })), "x y 3 true");
+    assert_eq!(render("fn f() {{{a}}}", json!({ "a": "body" })), "fn f() {body}");
+  }
+
+  #[test]
+  fn loops_with_positions() {
+    let source = "{{#each items}}{{@index}}:{{this}}{{#unless @last}}, {{/unless}}{{/each}}";
+    assert_eq!(render(source, json!({ "items": ["a", "b", "c"] })), "0:a, 1:b, 2:c");
+  }
+
+  #[test]
+  fn looks_up_outer_scopes() {
+    let source = "{{#each rules}}{{#each alternatives}}{{name}}{{this}} {{/each}}{{/each}}";
+    assert_eq!(render(source, json!({ "rules": [{ "name": "a", "alternatives": [1, 2] }] })), "a1 a2 ");
+  }
+
+  #[test]
+  fn conditions_with_else() {
+    let source = "{{#if x}}yes{{else}}no{{/if}}";
+    assert_eq!(render(source, json!({ "x": [1] })), "yes");
+    for value in [json!(false), json!(null), json!(0), json!(""), json!([])] {
+      assert_eq!(render(source, json!({ "x": value })), "no");
+    }
+  }
+
+  #[test]
+  fn applies_filters() {
+    let data = json!({ "a": "expr_rest", "b": "a-b.c" });
+    assert_eq!(render("{{a | upper}} {{a | capitalize}} {{a | pascal}}", data.clone()), "EXPR_REST Expr_rest ExprRest");
+    assert_eq!(render("{{b | identifier | lower}}", data), "a_b_c");
+  }
+
+  #[test]
+  fn removes_standalone_lines() {
+    let source = "start\n    {{#each items}}\n    {{this}}\n    {{/each}}\n{{! comment}}\nend\n";
+    assert_eq!(render(source, json!({ "items": ["a", "b"] })), "start\n    a\n    b\nend\n");
+  }
+
+  #[test]
+  fn indents_partials_and_values() {
+    let partials = [("body", "a\nb\n")];
+    assert_eq!(render_with("{\n    {{> body}}\n}\n", json!({}), &partials), "{\n    a\n    b\n}\n");
+    assert_eq!(render("  x = {{v}};", json!({ "v": "1\n2" })), "  x = 1\n  2;");
+  }
+
+  #[test]
+  fn reports_parse_errors() {
+    assert_eq!(parse_error("{{#each x}}"), "`#each` block is never closed");
+    assert_eq!(parse_error("{{#if x}}{{/each}}"), "expected `{{/if}}` to close the block opened at 1:1");
+    assert!(parse_error("{{x | shout}}").starts_with("unknown filter `shout`"));
+    assert_eq!(parse_error("{{x"), "`{{` is never closed by `}}`");
+  }
+
+  #[test]
+  fn reports_render_errors() {
+    let error = Template::parse("test", "{{missing}}").unwrap().render(&json!({}), &BTreeMap::new()).unwrap_err();
+    assert_eq!(error.message, "unknown name `missing`");
+    assert_eq!((error.line, error.col), (1, 1));
+
+    let error = Template::parse("test", "{{> nope}}").unwrap().render(&json!({}), &BTreeMap::new()).unwrap_err();
+    assert_eq!(error.message, "unknown partial `nope`");
+  }
+}
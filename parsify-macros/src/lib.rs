@@ -0,0 +1,221 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `grammar!` embeds a grammar in Rust source and expands to the parser parsify generates for it.
+//!
+//! ```
+//! mod expr {
+//!   parsify_macros::grammar! {
+//!     expr ::= term expr_rest;
+//!     expr_rest ::= "+" term expr_rest | ;
+//!     term ::= NUM | '(' expr ')';
+//!   }
+//! }
+//!
+//! struct Lexeme {
+//!   kind: &'static str,
+//!   span: std::ops::Range<usize>,
+//! }
+//!
+//! impl expr::Token for Lexeme {
+//!   fn kind(&self) -> &str {
+//!     self.kind
+//!   }
+//!
+//!   fn span(&self) -> std::ops::Range<usize> {
+//!     self.span.clone()
+//!   }
+//! }
+//!
+//! // (1 + 2)
+//! let lexemes = [("(", 0..1), ("NUM", 1..2), ("+", 3..4), ("NUM", 5..6), (")", 6..7)];
+//! let result = expr::Parser::new(lexemes.into_iter().map(|(kind, span)| Lexeme { kind, span })).parse();
+//! assert!(result.is_ok());
+//! ```
+//!
+//! `parsify` re-exports the macro as `parsify::grammar!` behind its `macros` feature.
+//!
+//! Terminals longer than one character must use double quotes, since Rust only accepts a single
+//! character between single quotes.
+
+use std::str::FromStr;
+use proc_macro::{Delimiter, Group, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use parsify_core::{Coord, Diagnostic, Grammar, Language};
+
+// a piece of the reconstructed grammar source and the Rust token it came from.
+struct Piece {
+  start: Coord,
+  end: Coord,
+  span: Span,
+}
+
+struct Source {
+  text: String,
+  pieces: Vec<Piece>,
+  line_num: usize,
+  col: usize,
+}
+
+impl Source {
+  fn new() -> Self {
+    Source { text: String::new(), pieces: vec![], line_num: 1, col: 1 }
+  }
+
+  fn push(&mut self, text: &str, span: Span, glue: bool) {
+    if !glue && !self.text.is_empty() {
+      self.text.push(' ');
+      self.col += 1;
+    }
+
+    let start = Coord { line_num: self.line_num, col: self.col };
+    self.text.push_str(text);
+    self.col += text.chars().count();
+
+    self.pieces.push(Piece { start, end: Coord { line_num: self.line_num, col: self.col }, span });
+  }
+
+  // one rule per line keeps the coords readable in error messages.
+  fn newline(&mut self) {
+    self.text.push('\n');
+    self.line_num += 1;
+    self.col = 1;
+  }
+
+  fn span_at(&self, at: Coord) -> Span {
+    self.pieces.iter()
+      .find(|x| x.start.line_num == at.line_num && x.start.col <= at.col && at.col < x.end.col.max(x.start.col + 1))
+      .or_else(|| self.pieces.iter().rev().find(|x| x.start.line_num <= at.line_num))
+      .map(|x| x.span)
+      .unwrap_or_else(Span::call_site)
+  }
+}
+
+fn collect_source(input: TokenStream, source: &mut Source) -> Result<(), (Span, String)> {
+  let mut glue_next = false;
+
+  for tree in input {
+    match &tree {
+      TokenTree::Ident(ident) => {
+        source.push(ident.to_string().as_str(), ident.span(), glue_next);
+        glue_next = false;
+      }
+      TokenTree::Literal(literal) => {
+        source.push(literal.to_string().as_str(), literal.span(), glue_next);
+        glue_next = false;
+      }
+      TokenTree::Punct(punct) => {
+        let character = punct.as_char();
+
+        // `<name>` and `a-b` identifiers are split into several Rust tokens.
+        let glue = glue_next || character == '>' || character == '-';
+        source.push(character.to_string().as_str(), punct.span(), glue);
+        glue_next = punct.spacing() == Spacing::Joint || character == '<' || character == '-';
+
+        if character == ';' || character == '.' {
+          source.newline();
+          glue_next = true;
+        }
+      }
+      TokenTree::Group(group) => {
+        return Err((group.span(), "parentheses, brackets and braces are not part of the grammar syntax, quote them as terminals".to_string()));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+  let mut result = TokenStream::new();
+
+  let mut tokens: Vec<TokenTree> = vec![
+    Punct::new(':', Spacing::Joint).into(),
+    Punct::new(':', Spacing::Alone).into(),
+    proc_macro::Ident::new("core", span).into(),
+    Punct::new(':', Spacing::Joint).into(),
+    Punct::new(':', Spacing::Alone).into(),
+    proc_macro::Ident::new("compile_error", span).into(),
+    Punct::new('!', Spacing::Alone).into(),
+  ];
+
+  let mut literal = Literal::string(message);
+  literal.set_span(span);
+  let mut group = Group::new(Delimiter::Parenthesis, TokenTree::from(literal).into());
+  group.set_span(span);
+  tokens.push(group.into());
+  tokens.push(Punct::new(';', Spacing::Alone).into());
+
+  for mut token in tokens {
+    token.set_span(span);
+    result.extend([token]);
+  }
+
+  result
+}
+
+fn diagnostic_error(source: &Source, diagnostic: &Diagnostic) -> TokenStream {
+  let mut message = format!("{}: {}", diagnostic.code, diagnostic.message);
+  let mut span = Span::call_site();
+
+  if let Some(label) = diagnostic.labels.iter().find(|x| x.is_primary) {
+    span = source.span_at(label.span.start);
+    message.push_str(format!("\n{}", label.message).as_str());
+  }
+
+  for note in &diagnostic.notes {
+    message.push_str(format!("\nnote: {}", note).as_str());
+  }
+
+  for help in &diagnostic.help {
+    message.push_str(format!("\nhelp: {}", help).as_str());
+  }
+
+  compile_error(span, message.as_str())
+}
+
+/// Expands to the Rust parser for the grammar written inside the braces: a `Token` trait for the
+/// scanner's tokens to implement, `ParserError` and a `Parser` reading from any token iterator.
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+  let mut source = Source::new();
+
+  if let Err((span, message)) = collect_source(input, &mut source) {
+    return compile_error(span, message.as_str());
+  }
+
+  let grammar = match Grammar::parse(source.text.as_str()) {
+    Ok(grammar) => grammar,
+    Err(error) => return error.diagnostics().iter().map(|x| diagnostic_error(&source, x)).collect(),
+  };
+
+  // conflicts are only warnings on the command line, but macros cannot emit warnings.
+  let conflicts: Vec<Diagnostic> = grammar.warnings().into_iter().filter(|x| x.code == "P0004").collect();
+  if !conflicts.is_empty() {
+    return conflicts.iter().map(|x| diagnostic_error(&source, x)).collect();
+  }
+
+  let output = match parsify_core::generate(&grammar, &Language::rust()) {
+    Ok(output) => output,
+    Err(error) => return compile_error(Span::call_site(), format!("parsify failed to generate the parser: {}", error).as_str()),
+  };
+
+  match TokenStream::from_str(output.as_str()) {
+    Ok(stream) => stream,
+    Err(error) => compile_error(Span::call_site(), format!("parsify generated invalid Rust: {}", error).as_str()),
+  }
+}
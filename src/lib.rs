@@ -0,0 +1,33 @@
+/*
+ * Parsify, a simple recursive descent parser generator.
+ * Copyright (C) 2024  Eduardo Ibarra
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parsify reads a BNF grammar, computes its nullable, FIRST, FOLLOW and predict sets, and
+//! generates a recursive descent parser for it in any language described by a [`Language`] spec.
+//!
+//! ```no_run
+//! let grammar = parsify::Grammar::parse("S ::= '(' S ')' S | ;").unwrap();
+//! let language = parsify::Language::from_json(&std::fs::read_to_string("langs/rust.json").unwrap()).unwrap();
+//! let output = parsify::generate(&grammar, &language).unwrap();
+//! ```
+
+pub use parsify_core::*;
+
+/// Expands to the Rust parser for the grammar written inside the braces, see the `parsify-macros`
+/// crate. Requires the `macros` feature.
+#[cfg(feature = "macros")]
+pub use parsify_macros::grammar;
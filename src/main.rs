@@ -1,4 +1,4 @@
-/**
+/*
  * Parsify, a simple recursive descent parser generator.
  * Copyright (C) 2024  Eduardo Ibarra
  *
@@ -16,73 +16,402 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::fs;
-use clap::Parser;
-use crate::error_handler::{print_parse_err, print_scan_error};
-use crate::language::Language;
-use crate::scanner::Scanner;
-
-mod scanner;
-mod productions;
-mod language;
-mod generator;
-mod parser;
-mod error_handler;
+use std::path::PathBuf;
+use std::{fs, process};
+use clap::{Parser, Subcommand};
+use parsify::formatter::{self, FormatError, FormatOptions, Layout, NameStyle, Operator, Terminator};
+use parsify::{lsp, ColorChoice, Feature, Grammar, IndentStyle, Language, LanguageLocation, LineEnding, MessageFormat, ReportFormat, Reporter, DEFAULT_FILE_STEM};
 
 /// Simple recursive descent parser generator.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+  #[command(subcommand)]
+  command: Option<Command>,
+
   /// Output file path
   #[arg(short, long)]
   output: Option<String>,
 
+  /// Write the generated files into this directory, named after the input file
+  #[arg(long, value_name = "DIR", conflicts_with = "output")]
+  out_dir: Option<String>,
+
   /// input file path
-  #[arg()]
-  input: String,
+  #[arg(required_unless_present = "explain")]
+  input: Option<String>,
 
-  /// output file's language
-  #[arg(short, long)]
-  lang: Option<String>,
+  /// output file's language, a built-in or installed language name or a spec file path
+  #[arg(short, long, default_value = "rust")]
+  lang: String,
+
+  /// Set a language option, like a package name, as NAME=VALUE
+  #[arg(long = "set", value_name = "NAME=VALUE")]
+  options: Vec<String>,
+
+  /// Turn on an optional generation mode the language implements
+  #[arg(long = "feature", value_enum, value_name = "FEATURE")]
+  features: Vec<Feature>,
+
+  /// indent generated code with spaces or tabs, overriding the language spec
+  #[arg(long, value_enum)]
+  indent_style: Option<IndentStyle>,
+
+  /// spaces per indentation level, overriding the language spec
+  #[arg(long, value_parser = clap::value_parser!(u8).range(1..))]
+  indent_width: Option<u8>,
+
+  /// line endings of generated code, overriding the language spec
+  #[arg(long, value_enum)]
+  line_ending: Option<LineEnding>,
+
+  /// Write the nullable, FIRST, FOLLOW and predict sets to this path ("-" for stdout)
+  #[arg(long)]
+  report: Option<String>,
+
+  /// format of the report written by --report
+  #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+  report_format: ReportFormat,
+
+  /// format of error and warning messages
+  #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+  message_format: MessageFormat,
+
+  /// when to color diagnostics
+  #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+  color: ColorChoice,
+
+  /// Print a detailed explanation of an error code and exit
+  #[arg(long, value_name = "CODE")]
+  explain: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Run a language server for grammar files over stdio
+  Lsp,
+  /// Reformat grammar files in place
+  Fmt(FmtArgs),
+  /// List the languages available to --lang
+  ListLangs,
+  /// Work with language spec files
+  #[command(subcommand)]
+  Lang(LangCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum LangCommand {
+  /// Validate a language spec and print the parser it generates for a sample grammar
+  Check {
+    /// spec file path or language name
+    spec: String,
+
+    /// when to color diagnostics
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+  },
+}
+
+#[derive(clap::Args, Debug)]
+struct FmtArgs {
+  /// grammar files to format
+  #[arg(required = true)]
+  files: Vec<String>,
+
+  /// Only check formatting, exit with 1 if a file would change
+  #[arg(long)]
+  check: bool,
+
+  /// rule definition operator
+  #[arg(long, value_enum, default_value_t = Operator::Bnf)]
+  operator: Operator,
+
+  /// rule terminator
+  #[arg(long, value_enum, default_value_t = Terminator::Semicolon)]
+  terminator: Terminator,
+
+  /// placement of `|` alternatives
+  #[arg(long, value_enum, default_value_t = Layout::Auto)]
+  layout: Layout,
+
+  /// line width used by the auto layout
+  #[arg(long, default_value_t = 80)]
+  width: usize,
+
+  /// spelling of non-terminal names
+  #[arg(long, value_enum, default_value_t = NameStyle::Keep)]
+  names: NameStyle,
+
+  /// Merge repeated definitions of a non-terminal into its first rule
+  #[arg(long)]
+  group_rules: bool,
+}
+
+fn format_files(args: FmtArgs) {
+  let options = FormatOptions {
+    operator: args.operator,
+    terminator: args.terminator,
+    layout: args.layout,
+    width: args.width,
+    names: args.names,
+    group_rules: args.group_rules,
+  };
+
+  let mut failed = false;
+
+  for path in args.files {
+    let file = fs::read_to_string(path.clone()).unwrap_or_else(|_| panic!("Failed to open file: {}", path));
+    let reporter = Reporter::new(MessageFormat::Human, ColorChoice::Auto.should_color(), path.clone(), &file);
+
+    let formatted = match formatter::format(&file, &options) {
+      Ok(formatted) => formatted,
+      Err(FormatError::Invalid(error)) => {
+        error.diagnostics().iter().for_each(|x| reporter.emit(x));
+        failed = true;
+        continue;
+      }
+      Err(error) => {
+        eprintln!("error: cannot format {}: {}", path, error);
+        failed = true;
+        continue;
+      }
+    };
+
+    if formatted == file {
+      continue;
+    }
+
+    if args.check {
+      println!("{} is not formatted", path);
+      failed = true;
+    } else if fs::write(path.clone(), formatted).is_err() {
+      eprintln!("error: failed to write {}", path);
+      failed = true;
+    }
+  }
+
+  if failed {
+    process::exit(1);
+  }
+}
+
+fn list_languages() {
+  let languages = Language::available();
+  let width = languages.iter().map(|x| x.name.len()).max().unwrap_or(0);
+
+  for language in languages {
+    let location = match language.location {
+      LanguageLocation::Builtin => "built-in".to_string(),
+      LanguageLocation::File(path) => path.display().to_string(),
+    };
+
+    println!("{:width$}  {}", language.name, location, width = width);
+  }
+}
+
+// exercises every construct a spec has to provide: calls, matches, alternatives and an empty production.
+const SAMPLE_GRAMMAR: &str = "\
+<list> ::= '(' <items> ')';
+<items> ::= <item> <items> | ;
+<item> ::= ID | NUM | <list>;
+";
+
+// alternatives sharing tokens, which `match-dispatch` has to give to the first alternative only.
+const CONFLICTING_GRAMMAR: &str = "\
+<call> ::= ID '(' ')' | ID | ID '[' ']' | NUM;
+";
+
+fn find_language(name_or_path: &str, color: ColorChoice) -> Language {
+  match Language::find(name_or_path) {
+    Ok(lang) => lang,
+    Err(error) => {
+      eprint!("{}", error.render(color.should_color()));
+      process::exit(1);
+    }
+  }
+}
+
+fn check_language(spec: &str, color: ColorChoice) {
+  let mut lang = find_language(spec, color);
+  let grammar = Grammar::parse(SAMPLE_GRAMMAR).unwrap();
+  let conflicting = Grammar::parse(CONFLICTING_GRAMMAR).unwrap();
+
+  // the templates' feature branches only run when the features are on.
+  lang.enabled = lang.features.clone();
+  for grammar in [&grammar, &conflicting] {
+    if let Err(error) = parsify::generate_files(grammar, &lang, DEFAULT_FILE_STEM) {
+      eprintln!("error: {}", error);
+      process::exit(1);
+    }
+  }
+
+  lang.enabled.clear();
+
+  match parsify::generate_files(&grammar, &lang, DEFAULT_FILE_STEM) {
+    Ok(files) => {
+      eprintln!("{} is a valid language spec", spec);
+      for (name, value) in &lang.options {
+        eprintln!("  option {} = {:?}", name, value);
+      }
+
+      for feature in &lang.features {
+        eprintln!("  feature {}", feature.as_str());
+      }
+
+      eprintln!("sample parser:");
+
+      for file in files {
+        if !file.suffix.is_empty() {
+          eprintln!("\n{}{}:", DEFAULT_FILE_STEM, file.suffix);
+        } else if !lang.files.is_empty() {
+          eprintln!("\n{}.{}:", DEFAULT_FILE_STEM, lang.extension);
+        }
+
+        eprintln!();
+        print!("{}", file.source);
+      }
+    }
+    Err(error) => {
+      eprintln!("error: {}", error);
+      process::exit(1);
+    }
+  }
 }
 
 fn main() {
   let cli_args = Args::parse();
 
-  // fixme: maybe make an install location for language specifications.
+  match cli_args.command {
+    Some(Command::Lsp) => {
+      if let Err(error) = lsp::run() {
+        eprintln!("error: language server failed: {}", error);
+        process::exit(1);
+      }
 
-  // default to rust language output.
-  let lang_json = fs::read_to_string(cli_args.lang.unwrap_or("./langs/rust.json".to_string())).unwrap();
-  let lang: Language = serde_json::from_str(lang_json.as_str()).unwrap();
-  let file = fs::read_to_string(cli_args.input.clone()).expect(format!("Failed to open file: {}", cli_args.input).as_str());
+      return;
+    }
+    Some(Command::Fmt(args)) => {
+      format_files(args);
+      return;
+    }
+    Some(Command::ListLangs) => {
+      list_languages();
+      return;
+    }
+    Some(Command::Lang(LangCommand::Check { spec, color })) => {
+      check_language(spec.as_str(), color);
+      return;
+    }
+    None => {}
+  }
 
-  let mut scanner = Scanner::new(file.clone());
-  let scanned_result = scanner.scan();
+  if let Some(code) = cli_args.explain {
+    match parsify::explain(code.as_str()) {
+      Some(explanation) => print!("{}", explanation),
+      None => {
+        eprintln!("error: no explanation for {}", code);
+        process::exit(1);
+      }
+    }
 
-  if scanned_result.is_err() {
-    print_scan_error(file, scanned_result.err().unwrap());
     return;
   }
 
-  let tokens = scanned_result.unwrap();
+  let input = cli_args.input.unwrap();
 
-  let mut parser = parser::Parser::new(tokens);
-  let non_terminals_wrapped = parser.parse();
+  let mut lang = find_language(cli_args.lang.as_str(), cli_args.color);
+  if let Some(style) = cli_args.indent_style {
+    lang.indent.style = style;
+  }
 
-  if non_terminals_wrapped.is_err() {
-    print_parse_err(file.clone(), non_terminals_wrapped.err().unwrap());
-    return;
+  if let Some(width) = cli_args.indent_width {
+    lang.indent.width = width.into();
+  }
+
+  if let Some(line_ending) = cli_args.line_ending {
+    lang.line_ending = line_ending;
+  }
+
+  for option in &cli_args.options {
+    let result = match option.split_once('=') {
+      Some((name, value)) => lang.set_option(name.trim(), value),
+      None => Err(format!("`--set {}` must have the form NAME=VALUE", option)),
+    };
+
+    if let Err(error) = result {
+      eprintln!("error: {}", error);
+      process::exit(1);
+    }
+  }
+
+  for feature in &cli_args.features {
+    if let Err(error) = lang.enable(*feature) {
+      eprintln!("error: {}", error);
+      process::exit(1);
+    }
+  }
+
+  let file = fs::read_to_string(input.clone()).unwrap_or_else(|_| panic!("Failed to open file: {}", input));
+
+  let reporter = Reporter::new(cli_args.message_format, cli_args.color.should_color(), input.clone(), &file);
+
+  let grammar = match Grammar::parse(&file) {
+    Ok(grammar) => grammar,
+    Err(error) => {
+      error.diagnostics().iter().for_each(|x| reporter.emit(x));
+      process::exit(1);
+    }
+  };
+
+  grammar.warnings().iter().for_each(|x| reporter.emit(x));
+
+  if let Some(report_path) = cli_args.report {
+    let report = grammar.report(cli_args.report_format);
+
+    if report_path == "-" {
+      print!("{}", report);
+    } else if fs::write(report_path, report).is_err() {
+      println!("Failed to write report!");
+    }
   }
 
-  let mut non_terminals = non_terminals_wrapped.unwrap();
+  let output_path = match cli_args.out_dir {
+    Some(dir) => {
+      if fs::create_dir_all(&dir).is_err() {
+        eprintln!("error: failed to create {}", dir);
+        process::exit(1);
+      }
 
-  productions::process(&mut non_terminals);
+      let mut file_name = match lang.output_file_stem() {
+        Ok(Some(file_stem)) => file_stem.into(),
+        Ok(None) => PathBuf::from(&input).file_stem().unwrap_or_default().to_os_string(),
+        Err(error) => {
+          eprintln!("error: {}", error);
+          process::exit(1);
+        }
+      };
+      if !lang.extension.is_empty() {
+        file_name.push(format!(".{}", lang.extension));
+      }
 
-  let output: String = generator::generate_parser(&non_terminals, &lang);
+      PathBuf::from(dir).join(file_name)
+    }
+    None => PathBuf::from(cli_args.output.unwrap_or("./output.txt".to_string())),
+  };
+  let file_stem = output_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
 
-  let result = fs::write(cli_args.output.unwrap_or("./output.txt".to_string()), output);
+  let files = match parsify::generate_files(&grammar, &lang, file_stem.as_str()) {
+    Ok(files) => files,
+    Err(error) => {
+      eprintln!("error: {}", error);
+      process::exit(1);
+    }
+  };
 
-  if result.is_err() {
-    println!("Failed to write to file!");
+  for file in files {
+    if fs::write(file.path(&output_path), file.source).is_err() {
+      println!("Failed to write to file!");
+    }
   }
 }